@@ -16,22 +16,6 @@ impl<'a> Iterator for AnnotatedStringIterator<'a> {
             return None;
         }
 
-        // Find the current active annotation
-        if let Some(annotation) = self
-            .annotated_string
-            .annotations
-            .iter()
-            .filter(|annotation| annotation.start < self.current && annotation.end > self.current)
-            .last()
-        {
-            let end = min(annotation.end, self.annotated_string.string.len());
-            let start = self.current;
-            self.current = end;
-            return Some(AnnotationStringPart {
-                string: &self.annotated_string.string[start..end],
-                annotation_type: Some(annotation.annotation_type),
-            });
-        }
         // Find the boundary of the nearest annotation
         let mut end = self.annotated_string.string.len();
         for annotation in &self.annotated_string.annotations {
@@ -39,12 +23,31 @@ impl<'a> Iterator for AnnotatedStringIterator<'a> {
                 end = annotation.start;
             }
         }
+        // Find the current active annotation, and the background it is drawn on.
+        // Later annotations take precedence, and the part ends where either of
+        // them ends or another annotation starts.
+        let find_active = |is_background: bool| {
+            self.annotated_string
+                .annotations
+                .iter()
+                .rfind(|annotation| {
+                    annotation.annotation_type.is_background() == is_background
+                        && annotation.start <= self.current
+                        && annotation.end > self.current
+                })
+        };
+        let annotation = find_active(false);
+        let background = find_active(true);
+        for active in annotation.iter().chain(&background) {
+            end = min(active.end, end);
+        }
         let start = self.current;
         self.current = end;
 
         Some(AnnotationStringPart {
             string: &self.annotated_string.string[start..end],
-            annotation_type: None,
+            annotation_type: annotation.map(|annotation| annotation.annotation_type),
+            background: background.map(|annotation| annotation.annotation_type),
         })
     }
 }
@@ -3,4 +3,6 @@ use super::AnnotationType;
 pub struct AnnotationStringPart<'a> {
     pub string: &'a str,
     pub annotation_type: Option<AnnotationType>,
+    // The background annotation the part is drawn on, if any.
+    pub background: Option<AnnotationType>,
 }
@@ -39,6 +39,22 @@ impl AnnotatedString {
         });
     }
 
+    pub fn push_str(&mut self, string: &str) {
+        self.string.push_str(string);
+    }
+
+    // Appends the string with the given annotation.
+    pub fn push_annotated(&mut self, string: &str, annotation_type: AnnotationType) {
+        let start = self.string.len();
+        self.string.push_str(string);
+        self.add_annotation(annotation_type, start, self.string.len());
+    }
+
+    // Draws the whole string on the given background annotation.
+    pub fn add_background(&mut self, annotation_type: AnnotationType) {
+        self.add_annotation(annotation_type, 0, self.string.len());
+    }
+
     pub fn truncate_left_until(&mut self, until: ByteIdx) {
         self.replace(0, until, "");
     }
@@ -98,7 +114,7 @@ impl AnnotatedString {
                 } else {
                     annotation.end.saturating_add(len_difference)
                 }
-            } else if annotation.end >= start {
+            } else if annotation.end > start {
                 // For annotations ending within the replaced range, we move the end index by
                 // the difference in length, constrained to the beginning or end of the replaced
                 // range.
@@ -3,7 +3,7 @@ use crate::prelude::*;
 
 // clippy::struct_field_names: naming the field `type` is disallowed due to type
 // being a keyword.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[allow(clippy::struct_field_names)]
 pub struct Annotation {
     pub annotation_type: AnnotationType,
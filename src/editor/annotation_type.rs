@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum AnnotationType {
     Match,
     SelectedMatch,
@@ -9,5 +9,27 @@ pub enum AnnotationType {
     Char,
     LifetimeSpecifier,
     Comment,
+    DocComment,
     String,
+    Escape,
+    Attribute,
+    Macro,
+    Selection,
+    MatchingBracket,
+    SecondaryCaret,
+    FoldMarker,
+    Occurrence,
+    Whitespace,
+    TrailingWhitespace,
+    IndentGuide,
+    Ruler,
+    CurrentLine,
+}
+
+impl AnnotationType {
+    // Whether annotations of this type stay beneath the annotations drawn on
+    // top of them, instead of being replaced by them.
+    pub const fn is_background(self) -> bool {
+        matches!(self, Self::CurrentLine)
+    }
 }
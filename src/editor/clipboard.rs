@@ -0,0 +1,50 @@
+use std::{cmp::min, collections::VecDeque};
+
+// The number of entries kept, matching the default size of the emacs kill
+// ring.
+const CAPACITY: usize = 60;
+
+#[derive(Clone, Default)]
+pub struct ClipboardEntry {
+    pub text: String,
+    // Whether the entry consists of whole lines, which are pasted above or below
+    // the caret line instead of at the caret.
+    pub linewise: bool,
+    // Whether the entry was copied from a block selection, in which case its
+    // lines are pasted below each other at the caret column.
+    pub block: bool,
+}
+
+// A ring of the most recently cut or copied entries, newest first.
+#[derive(Default)]
+pub struct Clipboard {
+    entries: VecDeque<ClipboardEntry>,
+}
+
+impl Clipboard {
+    pub fn set(&mut self, entry: ClipboardEntry) {
+        self.entries.push_front(entry);
+        self.entries.truncate(CAPACITY);
+    }
+
+    // Appends the entry to the newest one, so that consecutive kills can be
+    // pasted at once.
+    pub fn append(&mut self, entry: ClipboardEntry) {
+        if let Some(newest) = self.entries.front_mut() {
+            newest.text.push_str(&entry.text);
+            newest.linewise = newest.linewise && entry.linewise;
+            newest.block = newest.block && entry.block;
+        } else {
+            self.set(entry);
+        }
+    }
+
+    pub fn get(&self) -> Option<&ClipboardEntry> {
+        self.entries.front()
+    }
+
+    // Makes the next older entry the newest one, moving the newest to the back.
+    pub fn rotate(&mut self) {
+        self.entries.rotate_left(min(1, self.entries.len()));
+    }
+}
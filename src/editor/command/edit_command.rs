@@ -1,13 +1,50 @@
 use crossterm::event::{
-    KeyCode::{Backspace, Char, Delete, Enter, Tab},
+    KeyCode::{BackTab, Backspace, Char, Delete, Down, Enter, F, Tab, Up},
     KeyEvent, KeyModifiers,
 };
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Edit {
     Insert(char),
+    // Inserts text which may span multiple lines, such as a bracketed paste.
+    InsertText(String),
     InsertNewLine,
+    // Indents the selected lines, or inserts one level of indentation at the
+    // caret.
+    Indent,
+    // Removes one level of indentation from the selected lines, or the current
+    // line.
+    Dedent,
     Delete,
     DeleteBackward,
+    // Deletes up to the start of the next word, or the line break at the end
+    // of the line.
+    DeleteWordForward,
+    // Deletes back to the start of the previous word, or the line break at the
+    // start of the line.
+    DeleteWordBackward,
+    // The line operations below act on every line touched by the selection, or
+    // on the current line.
+    DeleteLines,
+    // Inserts a copy of the lines below them.
+    DuplicateLines,
+    MoveLinesUp,
+    MoveLinesDown,
+    // Appends the next line to the current one, or joins the selected lines,
+    // normalising the whitespace at each join to a single space.
+    JoinLines,
+    // Sorts the selected lines.
+    SortLines,
+    // Comments out the lines, or uncomments them if they are all commented.
+    ToggleComment,
+    Cut,
+    // Cuts the selection, appending it to the newest clipboard entry.
+    CutAppend,
+    Copy,
+    Paste,
+    PasteAfter,
+    // Replaces the text which was just pasted with the next older clipboard
+    // entry.
+    CyclePaste,
 }
 impl TryFrom<KeyEvent> for Edit {
     type Error = String;
@@ -17,10 +54,26 @@ impl TryFrom<KeyEvent> for Edit {
             (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
                 Ok(Self::Insert(character))
             }
-            (Tab, KeyModifiers::NONE) => Ok(Self::Insert('\t')),
+            (Tab, KeyModifiers::NONE) => Ok(Self::Indent),
+            (BackTab, _) => Ok(Self::Dedent),
             (Enter, KeyModifiers::NONE) => Ok(Self::InsertNewLine),
             (Backspace, KeyModifiers::NONE) => Ok(Self::DeleteBackward),
             (Delete, KeyModifiers::NONE) => Ok(Self::Delete),
+            (Backspace, KeyModifiers::CONTROL) => Ok(Self::DeleteWordBackward),
+            (Delete, KeyModifiers::CONTROL) => Ok(Self::DeleteWordForward),
+            (Char('k'), KeyModifiers::CONTROL) => Ok(Self::DeleteLines),
+            (Char('j'), KeyModifiers::CONTROL) => Ok(Self::JoinLines),
+            (Up, KeyModifiers::ALT) => Ok(Self::MoveLinesUp),
+            (Down, KeyModifiers::ALT) => Ok(Self::MoveLinesDown),
+            (Down, modifiers) if modifiers == KeyModifiers::ALT | KeyModifiers::SHIFT => {
+                Ok(Self::DuplicateLines)
+            }
+            (F(9), KeyModifiers::NONE) => Ok(Self::SortLines),
+            (Char('c'), KeyModifiers::CONTROL) => Ok(Self::Copy),
+            (Char('x'), KeyModifiers::CONTROL) => Ok(Self::Cut),
+            (Char('v'), KeyModifiers::CONTROL) => Ok(Self::Paste),
+            // Terminals without keyboard enhancements report Ctrl-/ as Ctrl-7
+            (Char('/' | '7'), KeyModifiers::CONTROL) => Ok(Self::ToggleComment),
             _ => Err(format!(
                 "Unsupported key code {:?} with modifiers {:?}",
                 event.code, event.modifiers
@@ -0,0 +1,54 @@
+use crossterm::event::{
+    KeyCode::{Char, F},
+    KeyEvent, KeyModifiers,
+};
+
+#[derive(Clone, Copy)]
+pub enum Fold {
+    // Closes the innermost open fold containing the caret line.
+    Close,
+    // Opens the closed fold whose header is the caret line.
+    Open,
+    // Opens the closed fold whose header is the caret line, or else closes the
+    // innermost fold containing it.
+    Toggle,
+    CloseAll,
+    OpenAll,
+}
+
+impl TryFrom<KeyEvent> for Fold {
+    type Error = String;
+
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+
+        if modifiers == KeyModifiers::NONE {
+            match code {
+                F(7) => Ok(Self::Toggle),
+                _ => Err(format!("Unsupported code: {code:?}")),
+            }
+        } else if modifiers == KeyModifiers::CONTROL {
+            match code {
+                F(7) => Ok(Self::CloseAll),
+                _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::SHIFT {
+            match code {
+                F(7) => Ok(Self::OpenAll),
+                _ => Err(format!("Unsupported SHIFT+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                Char('-') => Ok(Self::Close),
+                Char('=') => Ok(Self::Open),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
+        } else {
+            Err(format!(
+                "Unsupported key code {code:?} or modifiers {modifiers:?}"
+            ))
+        }
+    }
+}
@@ -5,17 +5,26 @@ use crossterm::event::Event;
 use crate::prelude::*;
 
 mod edit_command;
+mod fold;
+mod mouse;
 mod move_command;
+mod select;
 mod system;
 
 pub use edit_command::Edit;
+pub use fold::Fold;
+pub use mouse::Mouse;
 pub use move_command::Move;
-pub use system::System;
+pub use select::{Select, SelectionMode};
+pub use system::{DEFAULT_REGISTER, System};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Command {
     Move(Move),
     Edit(Edit),
+    Select(Select),
+    Fold(Fold),
+    Mouse(Mouse),
     System(System),
 }
 
@@ -30,8 +39,15 @@ impl TryFrom<Event> for Command {
             Event::Key(key_event) => Edit::try_from(key_event)
                 .map(Command::Edit)
                 .or_else(|_| Move::try_from(key_event).map(Command::Move))
+                .or_else(|_| Select::try_from(key_event).map(Command::Select))
+                .or_else(|_| Fold::try_from(key_event).map(Command::Fold))
                 .or_else(|_| System::try_from(key_event).map(Command::System))
                 .map_err(|_err| format!("Event not supported: {key_event:?}")),
+            // Terminals commonly send line breaks within pastes as carriage returns
+            Event::Paste(text) => Ok(Self::Edit(Edit::InsertText(
+                text.replace("\r\n", "\n").replace('\r', "\n"),
+            ))),
+            Event::Mouse(mouse_event) => Mouse::try_from(mouse_event).map(Command::Mouse),
             Event::Resize(width_u16, height_u16) => Ok(Self::System(System::Resize(Size {
                 height: height_u16 as usize,
                 width: width_u16 as usize,
@@ -0,0 +1,50 @@
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use crate::prelude::*;
+
+// Mouse input, with positions relative to the top left corner of the screen.
+#[derive(Clone, Copy)]
+pub enum Mouse {
+    // The left button was pressed.
+    Press(Position),
+    // The left button was pressed while holding Ctrl, which adds a caret.
+    AddCaret(Position),
+    // The mouse was moved while holding the left button.
+    Drag(Position),
+    // The mouse was moved while holding the left button and Alt, which selects a
+    // rectangle.
+    DragBlock(Position),
+    ScrollUp,
+    ScrollDown,
+}
+
+// clippy::as_conversions: Will run into problems for rare edge case systems
+// where usize < u16
+#[allow(clippy::as_conversions)]
+impl TryFrom<MouseEvent> for Mouse {
+    type Error = String;
+
+    fn try_from(event: MouseEvent) -> Result<Self, Self::Error> {
+        let position = Position {
+            col: event.column as usize,
+            row: event.row as usize,
+        };
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if event.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Ok(Self::AddCaret(position))
+            }
+            MouseEventKind::Down(MouseButton::Left) => Ok(Self::Press(position)),
+            MouseEventKind::Drag(MouseButton::Left)
+                if event.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                Ok(Self::DragBlock(position))
+            }
+            MouseEventKind::Drag(MouseButton::Left) => Ok(Self::Drag(position)),
+            MouseEventKind::ScrollUp => Ok(Self::ScrollUp),
+            MouseEventKind::ScrollDown => Ok(Self::ScrollDown),
+            _ => Err(format!("Unsupported mouse event: {:?}", event.kind)),
+        }
+    }
+}
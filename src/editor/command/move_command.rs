@@ -1,8 +1,11 @@
 use crossterm::event::{
-    KeyCode::{Down, End, Home, Left, PageDown, PageUp, Right, Up},
+    KeyCode::{Char, Down, End, F, Home, Left, PageDown, PageUp, Right, Up},
     KeyEvent, KeyModifiers,
 };
 
+use super::DEFAULT_REGISTER;
+use crate::prelude::*;
+
 #[derive(Clone, Copy)]
 pub enum Move {
     PageUp,
@@ -13,6 +16,20 @@ pub enum Move {
     Left,
     Right,
     Down,
+    FirstNonBlank,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    StartOfDocument,
+    EndOfDocument,
+    GoToLine(LineIdx),
+    // Jumps to the bracket matching the one at or in front of the caret.
+    MatchingBracket,
+    // Jumps to the named mark.
+    ToMark(char),
+    // Moves backward or forward through the locations left by jumps.
+    JumpBack,
+    JumpForward,
 }
 impl TryFrom<KeyEvent> for Move {
     type Error = String;
@@ -32,8 +49,25 @@ impl TryFrom<KeyEvent> for Move {
                 PageDown => Ok(Self::PageDown),
                 Home => Ok(Self::StartOfLine),
                 End => Ok(Self::EndOfLine),
+                F(2) => Ok(Self::ToMark(DEFAULT_REGISTER)),
                 _ => Err(format!("Unsupported code: {code:?}")),
             }
+        } else if modifiers == KeyModifiers::CONTROL {
+            match code {
+                Left => Ok(Self::WordBackward),
+                Right => Ok(Self::WordForward),
+                Home => Ok(Self::StartOfDocument),
+                End => Ok(Self::EndOfDocument),
+                // Terminals without keyboard enhancements report Ctrl-] as Ctrl-5
+                Char(']' | '5') => Ok(Self::MatchingBracket),
+                _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
+            }
+        } else if modifiers == KeyModifiers::ALT {
+            match code {
+                Left => Ok(Self::JumpBack),
+                Right => Ok(Self::JumpForward),
+                _ => Err(format!("Unsupported ALT+{code:?} combination")),
+            }
         } else {
             Err(format!(
                 "Unsupported key code {code:?} or modifiers {modifiers:?}"
@@ -0,0 +1,58 @@
+use crossterm::event::{
+    KeyCode::{Char, Down, Up},
+    KeyEvent, KeyModifiers,
+};
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum SelectionMode {
+    // Selects the text between the anchor and the caret.
+    Character,
+    // Like `Character`, but also includes the grapheme under the caret or the
+    // anchor, whichever comes last.
+    Inclusive,
+    // Selects every line touched by the selection.
+    Line,
+    // Selects the rectangle of screen columns spanned by the anchor and the
+    // caret, on every line between them.
+    Block,
+    // Like `Block`, but also includes the column of the grapheme under the
+    // caret or the anchor, whichever is further right.
+    InclusiveBlock,
+}
+
+#[derive(Clone, Copy)]
+pub enum Select {
+    Begin(SelectionMode),
+    Clear,
+    // Clears the selection and places the caret at its start.
+    CollapseToStart,
+    // Adds a caret on the line above the topmost caret, or below the bottommost
+    // one, at the column of the primary caret.
+    AddCaretAbove,
+    AddCaretBelow,
+    // Adds a caret at the next occurrence of the word under the primary caret.
+    AddCaretAtNextOccurrence,
+}
+
+impl TryFrom<KeyEvent> for Select {
+    type Error = String;
+
+    fn try_from(event: KeyEvent) -> Result<Self, Self::Error> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        match (code, modifiers) {
+            (Up, modifiers) if modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT => {
+                Ok(Self::AddCaretAbove)
+            }
+            (Down, modifiers) if modifiers == KeyModifiers::CONTROL | KeyModifiers::ALT => {
+                Ok(Self::AddCaretBelow)
+            }
+            (Char('d'), KeyModifiers::CONTROL) => Ok(Self::AddCaretAtNextOccurrence),
+            (Char('b'), KeyModifiers::CONTROL) => Ok(Self::Begin(SelectionMode::Block)),
+            _ => Err(format!(
+                "Unsupported key code {code:?} with modifiers {modifiers:?}"
+            )),
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use crossterm::event::{
-    KeyCode::{self, Char},
+    KeyCode::{self, Char, F},
     KeyEvent, KeyModifiers,
 };
 
 use crate::prelude::*;
 
+// The register used for macros and marks by key bindings which don't name
+// registers.
+pub const DEFAULT_REGISTER: char = 'q';
+
 #[derive(Clone, Copy)]
 pub enum System {
     Save,
@@ -12,6 +16,19 @@ pub enum System {
     Quit,
     Dismiss,
     Search,
+    SearchBackward,
+    CommandLine,
+    SwitchTheme,
+    ShowFileSettings,
+    // Shows or hides spaces, tabs and line ends.
+    ToggleWhitespace,
+    // Starts recording commands into the named register.
+    RecordMacro(char),
+    StopMacro,
+    // Replays the commands recorded in the named register.
+    PlayMacro(char),
+    // Sets the named mark at the caret.
+    SetMark(char),
 }
 
 impl TryFrom<KeyEvent> for System {
@@ -27,10 +44,20 @@ impl TryFrom<KeyEvent> for System {
                 Char('q') => Ok(Self::Quit),
                 Char('s') => Ok(Self::Save),
                 Char('f') => Ok(Self::Search),
+                Char('t') => Ok(Self::SwitchTheme),
+                Char('g') => Ok(Self::ShowFileSettings),
+                F(2) => Ok(Self::SetMark(DEFAULT_REGISTER)),
                 _ => Err(format!("Unsupported CONTROL+{code:?} combination")),
             }
-        } else if modifiers == KeyModifiers::NONE && matches!(code, KeyCode::Esc) {
-            Ok(Self::Dismiss)
+        } else if modifiers == KeyModifiers::NONE {
+            match code {
+                KeyCode::Esc => Ok(Self::Dismiss),
+                F(3) => Ok(Self::RecordMacro(DEFAULT_REGISTER)),
+                F(4) => Ok(Self::StopMacro),
+                F(5) => Ok(Self::PlayMacro(DEFAULT_REGISTER)),
+                F(6) => Ok(Self::ToggleWhitespace),
+                _ => Err(format!("Unsupported code: {code:?}")),
+            }
         } else {
             Err(format!(
                 "Unsupported key code {code:?} or modifier {modifiers:?}"
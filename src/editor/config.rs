@@ -0,0 +1,181 @@
+use std::{env, fs::read_to_string, io::ErrorKind, path::PathBuf};
+
+use super::Indentation;
+use crate::prelude::*;
+
+const FILE_NAME: &str = "config";
+
+#[derive(Default, Eq, PartialEq, Debug, Copy, Clone)]
+pub enum KeyBindings {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl TryFrom<&str> for KeyBindings {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "default" => Ok(Self::Default),
+            "vim" => Ok(Self::Vim),
+            "emacs" => Ok(Self::Emacs),
+            _ => Err(format!("Unknown key bindings: {value}")),
+        }
+    }
+}
+
+// Startup configuration, built from the configuration file and the command
+// line.
+pub struct Config {
+    pub key_bindings: KeyBindings,
+    pub theme: Option<String>,
+    // Whether the editor captures the mouse. Without capture, the terminal's
+    // own selection and copying can be used.
+    pub mouse: bool,
+    pub indentation: Indentation,
+    // Whether spaces, tabs and line ends are shown at startup.
+    pub show_whitespace: bool,
+    // Whether trailing whitespace is stripped on save. Editorconfig settings
+    // take precedence.
+    pub strip_trailing_whitespace: bool,
+    // The columns marked by a vertical ruler, counted from 1.
+    pub rulers: Vec<ColIdx>,
+    // Whether guides are drawn at each indentation level.
+    pub indent_guides: bool,
+    // Whether recorded macros are saved to the configuration directory, to be
+    // available in later sessions.
+    pub save_macros: bool,
+    pub file_name: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            key_bindings: KeyBindings::default(),
+            theme: None,
+            mouse: true,
+            indentation: Indentation::default(),
+            show_whitespace: false,
+            strip_trailing_whitespace: false,
+            rulers: Vec::new(),
+            indent_guides: true,
+            save_macros: false,
+            file_name: None,
+        }
+    }
+}
+
+impl Config {
+    // Reads the configuration file, if present, and applies the given arguments
+    // on top of it. The arguments must not include the program name.
+    pub fn load(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+        if let Some(path) = directory().map(|directory| directory.join(FILE_NAME)) {
+            match read_to_string(&path) {
+                Ok(contents) => config.apply_file(&contents)?,
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(format!("Could not read {}: {err}", path.display())),
+            }
+        }
+        config.apply_args(args)?;
+        Ok(config)
+    }
+
+    // Applies `key = value` lines. Empty lines and lines starting with `#` are
+    // ignored.
+    fn apply_file(&mut self, contents: &str) -> Result<(), String> {
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_number = idx.saturating_add(1);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Config line {line_number}: expected `key = value`"))?;
+            self.set(key.trim(), value.trim().trim_matches('"'))
+                .map_err(|err| format!("Config line {line_number}: {err}"))?;
+        }
+        Ok(())
+    }
+
+    // Applies `--key=value` options, the `--vim` and `--emacs` shortcuts and the
+    // name of the file to open.
+    fn apply_args(&mut self, args: impl Iterator<Item = String>) -> Result<(), String> {
+        for arg in args {
+            match arg.as_str() {
+                "--vim" => self.key_bindings = KeyBindings::Vim,
+                "--emacs" => self.key_bindings = KeyBindings::Emacs,
+                _ => {
+                    if let Some(option) = arg.strip_prefix("--") {
+                        let (key, value) = option
+                            .split_once('=')
+                            .ok_or_else(|| format!("Unknown option: {arg}"))?;
+                        self.set(&key.replace('-', "_"), value)?;
+                    } else {
+                        self.file_name = Some(arg);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "key_bindings" => self.key_bindings = KeyBindings::try_from(value)?,
+            "theme" => self.theme = Some(value.to_string()),
+            "mouse" => self.mouse = parse_bool(value)?,
+            "tab_width" => self.indentation.tab_width = parse_width(value)?,
+            "indent_width" => self.indentation.width = parse_width(value)?,
+            "soft_tabs" => self.indentation.soft_tabs = parse_bool(value)?,
+            "auto_indent" => self.indentation.auto_indent = parse_bool(value)?,
+            "save_macros" => self.save_macros = parse_bool(value)?,
+            "show_whitespace" => self.show_whitespace = parse_bool(value)?,
+            "strip_trailing_whitespace" => self.strip_trailing_whitespace = parse_bool(value)?,
+            "rulers" => self.rulers = parse_columns(value)?,
+            "indent_guides" => self.indent_guides = parse_bool(value)?,
+            _ => return Err(format!("Unknown setting: {key}")),
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "on" | "yes" => Ok(true),
+        "false" | "off" | "no" => Ok(false),
+        _ => Err(format!("Expected true or false, got: {value}")),
+    }
+}
+
+fn parse_width(value: &str) -> Result<ColIdx, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|&width| width > 0)
+        .ok_or_else(|| format!("Expected a positive width, got: {value}"))
+}
+
+// Parses a comma separated list of columns, which may be empty.
+fn parse_columns(value: &str) -> Result<Vec<ColIdx>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(parse_width)
+        .collect()
+}
+
+// Returns the directory holding the configuration file, user themes and
+// languages, and saved macros.
+pub fn directory() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .map(|directory| directory.join(NAME))
+}
@@ -1,4 +1,4 @@
-use super::FileType;
+use super::{FileType, vim::Mode};
 use crate::prelude::*;
 #[derive(Default, Eq, PartialEq, Debug)]
 pub struct DocumentStatus {
@@ -7,6 +7,9 @@ pub struct DocumentStatus {
     pub is_modified: bool,
     pub file_name: String,
     pub file_type: FileType,
+    pub mode: Option<Mode>,
+    // The register a macro is being recorded into.
+    pub recording: Option<char>,
 }
 
 impl DocumentStatus {
@@ -33,4 +36,14 @@ impl DocumentStatus {
     pub fn file_type_to_string(&self) -> String {
         self.file_type.to_string()
     }
+
+    pub fn mode_indicator_to_string(&self) -> String {
+        self.mode
+            .map_or_else(String::new, |mode| format!("-- {mode} -- "))
+    }
+
+    pub fn recording_indicator_to_string(&self) -> String {
+        self.recording
+            .map_or_else(String::new, |register| format!("recording @{register} "))
+    }
 }
@@ -0,0 +1,176 @@
+use std::mem::take;
+
+use crossterm::event::{Event, KeyCode::Char, KeyEvent, KeyModifiers};
+
+use super::{
+    command::{Command, DEFAULT_REGISTER, Edit, Move, Select, SelectionMode, System},
+    ui_components::View,
+};
+
+// Translates key events into commands following the emacs default bindings.
+// Keys without an emacs binding fall back to the regular key bindings.
+#[derive(Default)]
+pub struct Emacs {
+    // Whether the previous key was the `C-x` prefix.
+    awaiting_prefixed_key: bool,
+    // Whether the previous keys were `C-x r`, which prefixes register commands.
+    awaiting_register_command: bool,
+    // The register command waiting for a register name: `SPC` sets a mark and
+    // `j` jumps to it.
+    awaiting_register: Option<char>,
+    // Whether the previous key killed text, in which case a subsequent kill is
+    // appended to the same clipboard entry.
+    previous_was_kill: bool,
+}
+
+impl Emacs {
+    pub fn handle_key_event(&mut self, event: KeyEvent, view: &View) -> Vec<Command> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        let previous_was_kill = take(&mut self.previous_was_kill);
+        if let Some(register_command) = self.awaiting_register.take() {
+            let Char(register) = code else {
+                return Vec::new();
+            };
+            return if register_command == 'j' {
+                vec![Command::Move(Move::ToMark(register))]
+            } else {
+                vec![Command::System(System::SetMark(register))]
+            };
+        }
+        if take(&mut self.awaiting_register_command) {
+            if let (Char(register_command @ (' ' | 'j')), KeyModifiers::NONE) = (code, modifiers) {
+                self.awaiting_register = Some(register_command);
+            }
+            return Vec::new();
+        }
+        if take(&mut self.awaiting_prefixed_key) {
+            return match (code, modifiers) {
+                (Char('s'), KeyModifiers::CONTROL) => vec![Command::System(System::Save)],
+                (Char('c'), KeyModifiers::CONTROL) => vec![Command::System(System::Quit)],
+                // Like emacs' `rectangle-mark-mode`
+                (Char(' '), KeyModifiers::NONE) => {
+                    vec![Command::Select(Select::Begin(SelectionMode::Block))]
+                }
+                (Char('('), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    vec![Command::System(System::RecordMacro(DEFAULT_REGISTER))]
+                }
+                (Char(')'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    vec![Command::System(System::StopMacro)]
+                }
+                (Char('e'), KeyModifiers::NONE) => {
+                    vec![Command::System(System::PlayMacro(DEFAULT_REGISTER))]
+                }
+                (Char('r'), KeyModifiers::NONE) => {
+                    self.awaiting_register_command = true;
+                    Vec::new()
+                }
+                (Char('t'), KeyModifiers::NONE) => vec![Command::System(System::SwitchTheme)],
+                (Char('='), KeyModifiers::NONE) => {
+                    vec![Command::System(System::ShowFileSettings)]
+                }
+                _ => Vec::new(),
+            };
+        }
+        if modifiers == KeyModifiers::CONTROL {
+            self.handle_control_key(event, view, previous_was_kill)
+        } else if modifiers.contains(KeyModifiers::ALT) {
+            Self::handle_meta_key(event)
+        } else {
+            Command::try_from(Event::Key(event)).into_iter().collect()
+        }
+    }
+
+    fn handle_control_key(
+        &mut self,
+        event: KeyEvent,
+        view: &View,
+        previous_was_kill: bool,
+    ) -> Vec<Command> {
+        // Control with keys other than characters keeps the regular bindings
+        let Char(character) = event.code else {
+            return Command::try_from(Event::Key(event)).into_iter().collect();
+        };
+        let command = match character {
+            'a' => Command::Move(Move::StartOfLine),
+            'e' => Command::Move(Move::EndOfLine),
+            'n' => Command::Move(Move::Down),
+            'p' => Command::Move(Move::Up),
+            'f' => Command::Move(Move::Right),
+            'b' => Command::Move(Move::Left),
+            'v' => Command::Move(Move::PageDown),
+            'd' => Command::Edit(Edit::Delete),
+            'y' => Command::Edit(Edit::Paste),
+            's' => Command::System(System::Search),
+            'r' => Command::System(System::SearchBackward),
+            ' ' | '@' => Command::Select(Select::Begin(SelectionMode::Character)),
+            'g' => {
+                return vec![
+                    Command::Select(Select::Clear),
+                    Command::System(System::Dismiss),
+                ];
+            }
+            'k' => return self.kill_line(view, previous_was_kill),
+            'w' => return self.kill(Vec::new(), previous_was_kill),
+            'x' => {
+                self.awaiting_prefixed_key = true;
+                return Vec::new();
+            }
+            _ => return Vec::new(),
+        };
+        vec![command]
+    }
+
+    fn handle_meta_key(event: KeyEvent) -> Vec<Command> {
+        match event.code {
+            Char('f') => vec![Command::Move(Move::WordForward)],
+            Char('b') => vec![Command::Move(Move::WordBackward)],
+            Char('v') => vec![Command::Move(Move::PageUp)],
+            Char('<') => vec![Command::Move(Move::StartOfDocument)],
+            Char('>') => vec![Command::Move(Move::EndOfDocument)],
+            Char('y') => vec![Command::Edit(Edit::CyclePaste)],
+            Char('w') => vec![Command::Edit(Edit::Copy), Command::Select(Select::Clear)],
+            Char(';') => vec![Command::Edit(Edit::ToggleComment)],
+            // Like emacs' `xref-go-forward` and `xref-go-back`
+            Char(',') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                vec![Command::Move(Move::JumpForward)]
+            }
+            Char(',') => vec![Command::Move(Move::JumpBack)],
+            // Folding keeps the regular bindings
+            Char('-' | '=') => Command::try_from(Event::Key(event)).into_iter().collect(),
+            // Meta with arrow keys moves and duplicates lines
+            Char(_) => Vec::new(),
+            _ => Command::try_from(Event::Key(event)).into_iter().collect(),
+        }
+    }
+
+    // Kills the rest of the line, or the line break if the caret is at the end of
+    // the line.
+    fn kill_line(&mut self, view: &View, previous_was_kill: bool) -> Vec<Command> {
+        let location = view.text_location();
+        let movement = if location.grapheme_idx < view.grapheme_count(location.line_idx) {
+            Move::EndOfLine
+        } else {
+            Move::Right
+        };
+        self.kill(
+            vec![
+                Command::Select(Select::Begin(SelectionMode::Character)),
+                Command::Move(movement),
+            ],
+            previous_was_kill,
+        )
+    }
+
+    fn kill(&mut self, mut commands: Vec<Command>, previous_was_kill: bool) -> Vec<Command> {
+        let cut = if previous_was_kill {
+            Edit::CutAppend
+        } else {
+            Edit::Cut
+        };
+        commands.push(Command::Edit(cut));
+        self.previous_was_kill = true;
+        commands
+    }
+}
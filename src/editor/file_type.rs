@@ -1,16 +1,87 @@
-use std::fmt::{Display, Formatter, Result};
+use std::{
+    fmt::{Display, Formatter, Result},
+    path::Path,
+};
+
+use super::language::{Language, Languages};
 
 #[derive(Default, Eq, PartialEq, Debug, Copy, Clone)]
 pub enum FileType {
     Rust,
+    // A language highlighted according to its definition file.
+    Language(&'static Language),
     #[default]
     Text,
 }
 
+impl FileType {
+    pub fn from_path(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"))
+        {
+            Self::Rust
+        } else {
+            Languages::for_path(path).map_or(Self::Text, Self::Language)
+        }
+    }
+
+    // Whether a line ending in the given character opens a block, so that the
+    // following line is indented one level deeper.
+    pub fn opens_block(self, character: char) -> bool {
+        match self {
+            Self::Rust => character == '{',
+            Self::Language(language) => language.opens_block(character),
+            Self::Text => false,
+        }
+    }
+
+    // The pairs whose closing character is inserted along with the opening one.
+    pub fn auto_close_pairs(self) -> &'static [(char, char)] {
+        match self {
+            Self::Rust => &[('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')],
+            Self::Language(language) => language.auto_close_pairs(),
+            Self::Text => &[],
+        }
+    }
+
+    // The marker starting a comment which runs to the end of the line.
+    pub fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Self::Rust => Some("//"),
+            Self::Language(language) => language.line_comments().first().map(String::as_str),
+            Self::Text => None,
+        }
+    }
+
+    // The tree-sitter grammar and highlights query parsing this file type, if
+    // any. File types without one keep their line based highlighter.
+    #[cfg(feature = "tree-sitter")]
+    pub fn tree_sitter_grammar(self) -> Option<(tree_sitter::Language, &'static str)> {
+        match self {
+            Self::Rust => Some((
+                tree_sitter_rust::LANGUAGE.into(),
+                tree_sitter_rust::HIGHLIGHTS_QUERY,
+            )),
+            Self::Language(_) | Self::Text => None,
+        }
+    }
+
+    // The markers opening and closing a block comment.
+    pub fn block_comment(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Rust => Some(("/*", "*/")),
+            Self::Language(language) => language.block_comment(),
+            Self::Text => None,
+        }
+    }
+}
+
 impl Display for FileType {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
         match self {
             Self::Rust => write!(formatter, "Rust"),
+            Self::Language(language) => write!(formatter, "{}", language.name()),
             Self::Text => write!(formatter, "Text"),
         }
     }
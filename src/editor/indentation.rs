@@ -0,0 +1,36 @@
+use crate::prelude::*;
+
+// How lines are indented and how tabs are displayed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Indentation {
+    // Whether indenting inserts spaces instead of a tab.
+    pub soft_tabs: bool,
+    // The number of columns per indentation level.
+    pub width: ColIdx,
+    // The distance between tab stops.
+    pub tab_width: ColIdx,
+    // Whether new lines start with the indentation of the previous line.
+    pub auto_indent: bool,
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Self {
+            soft_tabs: true,
+            width: DEFAULT_TAB_WIDTH,
+            tab_width: DEFAULT_TAB_WIDTH,
+            auto_indent: true,
+        }
+    }
+}
+
+impl Indentation {
+    // Returns the whitespace of a single indentation level.
+    pub fn unit(&self) -> String {
+        if self.soft_tabs {
+            " ".repeat(self.width)
+        } else {
+            "\t".to_string()
+        }
+    }
+}
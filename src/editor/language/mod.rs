@@ -0,0 +1,315 @@
+use std::{
+    fs::{read_dir, read_to_string},
+    path::Path,
+    sync::OnceLock,
+};
+
+use super::config;
+
+mod number_format;
+
+use number_format::NumberFormat;
+
+const FILE_EXTENSION: &str = "lang";
+const DIRECTORY_NAME: &str = "languages";
+
+const BUILT_IN_LANGUAGES: [(&str, &str); 7] = [
+    ("c", include_str!("languages/c.lang")),
+    ("json", include_str!("languages/json.lang")),
+    ("markdown", include_str!("languages/markdown.lang")),
+    ("python", include_str!("languages/python.lang")),
+    ("shell", include_str!("languages/shell.lang")),
+    ("toml", include_str!("languages/toml.lang")),
+    ("yaml", include_str!("languages/yaml.lang")),
+];
+
+// Loaded once, on first use
+static LANGUAGES: OnceLock<Languages> = OnceLock::new();
+
+// The definition of a language for the generic syntax highlighter. Language
+// files contain one `key = value` line per setting, where the value is a
+// whitespace separated list. Empty lines and lines starting with `#` are
+// ignored.
+#[derive(Default, Debug)]
+pub struct Language {
+    name: String,
+    // Matched against the extension of a file, ignoring case.
+    extensions: Vec<String>,
+    // Matched against the full name of files without a telling extension,
+    // such as `.bashrc`.
+    file_names: Vec<String>,
+    keywords: Vec<String>,
+    types: Vec<String>,
+    known_values: Vec<String>,
+    line_comments: Vec<String>,
+    block_comment: Option<(String, String)>,
+    // Delimiters of strings which end with the line.
+    strings: Vec<String>,
+    // Delimiters of strings which may span several lines.
+    multiline_strings: Vec<String>,
+    escape: Option<char>,
+    number_formats: Vec<NumberFormat>,
+    digit_separator: Option<char>,
+    // Suffixes allowed behind a number, such as the `u` in `10u`.
+    number_suffixes: Vec<String>,
+    // Markers which highlight the whole line when they start it, such as
+    // headings in Markdown, tables in TOML or preprocessor directives in C.
+    line_markers: Vec<String>,
+    block_openers: Vec<char>,
+    auto_close_pairs: Vec<(char, char)>,
+}
+
+impl Language {
+    pub fn parse(name: &str, source: &str) -> Result<Self, String> {
+        let mut language = Self {
+            name: name.to_string(),
+            ..Self::default()
+        };
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_number = idx.saturating_add(1);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{name} line {line_number}: expected `key = value`"))?;
+            language
+                .set(key.trim(), value.trim())
+                .map_err(|err| format!("{name} line {line_number}: {err}"))?;
+        }
+        Ok(language)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let words = || value.split_whitespace().map(str::to_string).collect();
+        match key {
+            "name" => value.clone_into(&mut self.name),
+            "extensions" => self.extensions = words(),
+            "file_names" => self.file_names = words(),
+            "keywords" => self.keywords = words(),
+            "types" => self.types = words(),
+            "known_values" => self.known_values = words(),
+            "line_comments" => self.line_comments = words(),
+            "block_comment" => {
+                let mut markers = value.split_whitespace();
+                match (markers.next(), markers.next(), markers.next()) {
+                    (Some(open), Some(close), None) => {
+                        self.block_comment = Some((open.to_string(), close.to_string()));
+                    }
+                    _ => return Err("expected an opening and a closing marker".to_string()),
+                }
+            }
+            "strings" => self.strings = words(),
+            "multiline_strings" => self.multiline_strings = words(),
+            "escape" => self.escape = Some(parse_char(value)?),
+            "numbers" => {
+                self.number_formats = value
+                    .split_whitespace()
+                    .map(NumberFormat::try_from)
+                    .collect::<Result<_, _>>()?;
+            }
+            "digit_separator" => self.digit_separator = Some(parse_char(value)?),
+            "number_suffixes" => self.number_suffixes = words(),
+            "line_markers" => self.line_markers = words(),
+            "block_openers" => {
+                self.block_openers = value
+                    .split_whitespace()
+                    .map(parse_char)
+                    .collect::<Result<_, _>>()?;
+            }
+            "auto_close" => {
+                self.auto_close_pairs = value
+                    .split_whitespace()
+                    .map(parse_pair)
+                    .collect::<Result<_, _>>()?;
+            }
+            _ => return Err(format!("Unknown key: {key}")),
+        }
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        let extension_matches = path.extension().is_some_and(|extension| {
+            self.extensions
+                .iter()
+                .any(|candidate| extension.eq_ignore_ascii_case(candidate))
+        });
+        extension_matches
+            || path.file_name().is_some_and(|file_name| {
+                self.file_names
+                    .iter()
+                    .any(|candidate| file_name == candidate.as_str())
+            })
+    }
+
+    pub fn is_keyword(&self, word: &str) -> bool {
+        self.keywords.iter().any(|keyword| keyword == word)
+    }
+
+    pub fn is_type(&self, word: &str) -> bool {
+        self.types.iter().any(|candidate| candidate == word)
+    }
+
+    pub fn is_known_value(&self, word: &str) -> bool {
+        self.known_values.iter().any(|value| value == word)
+    }
+
+    pub fn line_comments(&self) -> &[String] {
+        &self.line_comments
+    }
+
+    pub fn block_comment(&self) -> Option<(&str, &str)> {
+        self.block_comment
+            .as_ref()
+            .map(|(open, close)| (open.as_str(), close.as_str()))
+    }
+
+    pub fn strings(&self) -> &[String] {
+        &self.strings
+    }
+
+    pub fn multiline_strings(&self) -> &[String] {
+        &self.multiline_strings
+    }
+
+    pub const fn escape(&self) -> Option<char> {
+        self.escape
+    }
+
+    pub fn line_markers(&self) -> &[String] {
+        &self.line_markers
+    }
+
+    pub fn opens_block(&self, character: char) -> bool {
+        self.block_openers.contains(&character)
+    }
+
+    pub fn auto_close_pairs(&self) -> &[(char, char)] {
+        &self.auto_close_pairs
+    }
+
+    // Whether the word is a number in one of the formats of this language,
+    // optionally followed by one of its suffixes.
+    pub fn is_number(&self, word: &str) -> bool {
+        self.is_unsuffixed_number(word)
+            || self.number_suffixes.iter().any(|suffix| {
+                word.len() > suffix.len()
+                    && word
+                        .get(word.len().saturating_sub(suffix.len())..)
+                        .is_some_and(|end| end.eq_ignore_ascii_case(suffix))
+                    && word
+                        .get(..word.len().saturating_sub(suffix.len()))
+                        .is_some_and(|number| self.is_unsuffixed_number(number))
+            })
+    }
+
+    fn is_unsuffixed_number(&self, word: &str) -> bool {
+        self.number_formats
+            .iter()
+            .any(|format| format.matches(word, self.digit_separator))
+    }
+}
+
+// Languages are identified by their name.
+impl PartialEq for Language {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Language {}
+
+fn parse_char(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(char), None) => Ok(char),
+        _ => Err(format!("Expected a single character: {value}")),
+    }
+}
+
+fn parse_pair(value: &str) -> Result<(char, char), String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(open), Some(close), None) => Ok((open, close)),
+        _ => Err(format!("Expected a pair of characters: {value}")),
+    }
+}
+
+// All languages known to the generic syntax highlighter: the built-in ones,
+// plus the `.lang` files in the `languages` folder of the config directory.
+// User languages replace built-in languages of the same file name.
+#[derive(Default)]
+pub struct Languages {
+    languages: Vec<(String, Language)>,
+}
+
+impl Languages {
+    // Loads all languages, unless they have been loaded already. Languages
+    // which fail to load are skipped and reported in the returned list of
+    // errors.
+    pub fn initialize() -> Vec<String> {
+        let mut errors = Vec::new();
+        LANGUAGES.get_or_init(|| Self::load(&mut errors));
+        errors
+    }
+
+    fn load(errors: &mut Vec<String>) -> Self {
+        let mut languages = Self::default();
+        for (name, source) in BUILT_IN_LANGUAGES {
+            match Language::parse(name, source) {
+                Ok(language) => languages.insert(name, language),
+                Err(err) => errors.push(err),
+            }
+        }
+        if let Some(directory) = config::directory() {
+            languages.load_directory(&directory.join(DIRECTORY_NAME), errors);
+        }
+        languages
+    }
+
+    fn load_directory(&mut self, directory: &Path, errors: &mut Vec<String>) {
+        let Ok(entries) = read_dir(directory) else {
+            return;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path
+                .extension()
+                .is_none_or(|extension| extension != FILE_EXTENSION)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let language = read_to_string(&path)
+                .map_err(|err| format!("Could not read {}: {err}", path.display()))
+                .and_then(|source| Language::parse(name, &source));
+            match language {
+                Ok(language) => self.insert(name, language),
+                Err(err) => errors.push(err),
+            }
+        }
+    }
+
+    fn insert(&mut self, name: &str, language: Language) {
+        self.languages.retain(|(existing, _)| existing != name);
+        self.languages.push((name.to_string(), language));
+    }
+
+    // Returns the language of the file at the given path. User languages take
+    // precedence, since they are loaded last.
+    pub fn for_path(path: &Path) -> Option<&'static Language> {
+        LANGUAGES
+            .get_or_init(|| Self::load(&mut Vec::new()))
+            .languages
+            .iter()
+            .rev()
+            .map(|(_, language)| language)
+            .find(|language| language.matches(path))
+    }
+}
@@ -0,0 +1,144 @@
+// The ways a language may write a number.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NumberFormat {
+    // Digits, such as `42`
+    Decimal,
+    // Digits with a fractional part, such as `4.2`
+    Float,
+    // Digits with an optional fractional part and an exponent, such as `4.2e1`
+    Exponent,
+    // `0x` followed by hexadecimal digits
+    Hex,
+    // `0o` followed by octal digits
+    Octal,
+    // `0b` followed by binary digits
+    Binary,
+}
+
+impl NumberFormat {
+    // Whether the word is a number in this format. The digit separator may
+    // appear between digits.
+    pub fn matches(self, word: &str, separator: Option<char>) -> bool {
+        match self {
+            Self::Decimal => is_digits(word, 10, separator),
+            Self::Float => word.split_once('.').is_some_and(|(whole, fraction)| {
+                is_digits(whole, 10, separator) && is_digits(fraction, 10, separator)
+            }),
+            Self::Exponent => word
+                .split_once(['e', 'E'])
+                .is_some_and(|(mantissa, exponent)| {
+                    (Self::Decimal.matches(mantissa, separator)
+                        || Self::Float.matches(mantissa, separator))
+                        && is_digits(exponent, 10, separator)
+                }),
+            Self::Hex => is_prefixed(word, 'x', 16, separator),
+            Self::Octal => is_prefixed(word, 'o', 8, separator),
+            Self::Binary => is_prefixed(word, 'b', 2, separator),
+        }
+    }
+}
+
+impl TryFrom<&str> for NumberFormat {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "decimal" => Ok(Self::Decimal),
+            "float" => Ok(Self::Float),
+            "exponent" => Ok(Self::Exponent),
+            "hex" => Ok(Self::Hex),
+            "octal" => Ok(Self::Octal),
+            "binary" => Ok(Self::Binary),
+            _ => Err(format!("Unknown number format: {value}")),
+        }
+    }
+}
+
+// Whether the word consists of digits in the given base, with separators only
+// between digits.
+fn is_digits(word: &str, base: u32, separator: Option<char>) -> bool {
+    let mut prev_was_digit = false;
+    for char in word.chars() {
+        if char.is_digit(base) {
+            prev_was_digit = true;
+        } else if Some(char) == separator && prev_was_digit {
+            prev_was_digit = false;
+        } else {
+            return false;
+        }
+    }
+    prev_was_digit
+}
+
+fn is_prefixed(word: &str, marker: char, base: u32, separator: Option<char>) -> bool {
+    let mut chars = word.chars();
+    chars.next() == Some('0')
+        && chars
+            .next()
+            .is_some_and(|char| char.eq_ignore_ascii_case(&marker))
+        && is_digits(chars.as_str(), base, separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimals_and_floats_need_digits_on_both_sides() {
+        assert!(NumberFormat::Decimal.matches("42", None));
+        assert!(!NumberFormat::Decimal.matches("4.2", None));
+        assert!(!NumberFormat::Decimal.matches("", None));
+        assert!(NumberFormat::Float.matches("4.2", None));
+        assert!(!NumberFormat::Float.matches("4.", None));
+        assert!(!NumberFormat::Float.matches(".2", None));
+        assert!(!NumberFormat::Float.matches("4.2.1", None));
+    }
+
+    #[test]
+    fn exponents_follow_decimals_or_floats() {
+        assert!(NumberFormat::Exponent.matches("4e2", None));
+        assert!(NumberFormat::Exponent.matches("4.2E10", None));
+        assert!(!NumberFormat::Exponent.matches("e2", None));
+        assert!(!NumberFormat::Exponent.matches("4e", None));
+        assert!(!NumberFormat::Exponent.matches("4.e2", None));
+    }
+
+    #[test]
+    fn prefixed_numbers_use_the_digits_of_their_base() {
+        assert!(NumberFormat::Hex.matches("0xFf", None));
+        assert!(NumberFormat::Hex.matches("0XA0", None));
+        assert!(!NumberFormat::Hex.matches("0xg", None));
+        assert!(!NumberFormat::Hex.matches("0x", None));
+        assert!(!NumberFormat::Hex.matches("1xA", None));
+        assert!(NumberFormat::Octal.matches("0o17", None));
+        assert!(!NumberFormat::Octal.matches("0o18", None));
+        assert!(NumberFormat::Binary.matches("0b1010", None));
+        assert!(!NumberFormat::Binary.matches("0b102", None));
+    }
+
+    #[test]
+    fn separators_only_appear_between_digits() {
+        let separator = Some('_');
+        assert!(NumberFormat::Decimal.matches("1_000", separator));
+        assert!(!NumberFormat::Decimal.matches("1__0", separator));
+        assert!(!NumberFormat::Decimal.matches("_1", separator));
+        assert!(!NumberFormat::Decimal.matches("1_", separator));
+        assert!(!NumberFormat::Decimal.matches("1_000", None));
+        assert!(NumberFormat::Float.matches("1_0.2_5", separator));
+        assert!(!NumberFormat::Float.matches("1_.5", separator));
+        assert!(NumberFormat::Exponent.matches("1_0e1_0", separator));
+        assert!(NumberFormat::Hex.matches("0xdead_beef", separator));
+        assert!(!NumberFormat::Hex.matches("0x_1", separator));
+        assert!(NumberFormat::Decimal.matches("1'000", Some('\'')));
+    }
+
+    #[test]
+    fn names_parse_to_formats() {
+        assert_eq!(NumberFormat::try_from("hex"), Ok(NumberFormat::Hex));
+        assert_eq!(
+            NumberFormat::try_from("exponent"),
+            Ok(NumberFormat::Exponent)
+        );
+        assert!(NumberFormat::try_from("roman").is_err());
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CharacterClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+impl From<&str> for CharacterClass {
+    fn from(grapheme: &str) -> Self {
+        if grapheme.trim().is_empty() {
+            Self::Whitespace
+        } else if grapheme
+            .chars()
+            .any(|char| char.is_alphanumeric() || char == '_')
+        {
+            Self::Word
+        } else {
+            Self::Punctuation
+        }
+    }
+}
@@ -6,15 +6,17 @@ use std::{
 
 use crate::prelude::*;
 
+mod character_class;
 mod grapheme_width;
 mod text_fragment;
 
+use character_class::CharacterClass;
 use grapheme_width::GraphemeWidth;
 use text_fragment::TextFragment;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-use super::{AnnotatedString, Annotation};
+use super::{AnnotatedString, Annotation, AnnotationType};
 
 #[derive(Default, Clone)]
 pub struct Line {
@@ -82,11 +84,52 @@ impl Line {
         }
     }
 
+    // Returns the marker drawn for spaces and tabs when whitespace is shown. Tabs
+    // are filled up to the next tab stop after their arrow.
+    fn get_whitespace_marker(for_str: &str, width: ColIdx) -> Option<String> {
+        match for_str {
+            " " => Some("·".to_string()),
+            "\t" => Some(format!("→{}", " ".repeat(width.saturating_sub(1)))),
+            _ => None,
+        }
+    }
+
+    // Returns the guide drawn for whitespace at an indentation level. Tabs are
+    // filled up to the next tab stop after the guide.
+    fn get_indent_guide(for_str: &str, width: ColIdx) -> String {
+        debug_assert!(for_str == " " || for_str == "\t");
+        format!("│{}", " ".repeat(width.saturating_sub(1)))
+    }
+
+    // Returns the indices of the fragments within the leading whitespace which
+    // start at a multiple of the indentation width.
+    fn indent_guide_fragments(&self, widths: &[ColIdx], indent_width: ColIdx) -> Vec<usize> {
+        let indent_width = indent_width.max(1);
+        let mut col: ColIdx = 0;
+        let mut result = Vec::new();
+        for (fragment_idx, (fragment, &width)) in self
+            .fragments
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .take(self.first_non_blank())
+        {
+            if col.is_multiple_of(indent_width)
+                && (fragment.grapheme == " " || fragment.grapheme == "\t")
+            {
+                result.push(fragment_idx);
+            }
+            col = col.saturating_add(width);
+        }
+        result
+    }
+
     // Gets the visible graphemes in the given column index.
     // Note that the column index is not the same as the grapheme index:
     // A grapheme can have a width of 2 columns.
-    pub fn get_visible_graphemes(&self, range: Range<ColIdx>) -> String {
-        self.get_annotated_visible_sub_str(range, None).to_string()
+    pub fn get_visible_graphemes(&self, range: Range<ColIdx>, tab_width: ColIdx) -> String {
+        self.get_annotated_visible_sub_str(range, None, tab_width, false, None)
+            .to_string()
     }
 
     // Gets the annotated string in the given column index.
@@ -97,16 +140,38 @@ impl Line {
     // - query: The query to highlight in the annotated string.
     // - selected_match: The selected match to highlight on the annotated string.
     //   This is only applied if the query is not empty
+    // - tab_width: The distance between tab stops.
+    // - show_whitespace: Whether spaces and tabs are drawn as `·` and `→`.
+    // - indent_guides: The indentation width, at whose multiples guides are drawn
+    //   within the leading whitespace. None draws no guides.
     pub fn get_annotated_visible_sub_str(
         &self,
         range: Range<ColIdx>,
         annotations: Option<&Vec<Annotation>>,
+        tab_width: ColIdx,
+        show_whitespace: bool,
+        indent_guides: Option<ColIdx>,
     ) -> AnnotatedString {
         if range.start >= range.end {
             return AnnotatedString::default();
         }
         // Create a new annotated string
         let mut result = AnnotatedString::from(&self.string);
+        let widths: Vec<ColIdx> = self.fragment_widths(tab_width).collect();
+
+        // Indent guides come first, so that any other annotation takes precedence
+        let guides = indent_guides.map_or_else(Vec::new, |indent_width| {
+            self.indent_guide_fragments(&widths, indent_width)
+        });
+        for &fragment_idx in &guides {
+            if let Some(fragment) = self.fragments.get(fragment_idx) {
+                result.add_annotation(
+                    AnnotationType::IndentGuide,
+                    fragment.start,
+                    fragment.start.saturating_add(fragment.grapheme.len()),
+                );
+            }
+        }
 
         // Apply annotations for this string
         if let Some(annotations) = annotations {
@@ -119,10 +184,13 @@ impl Line {
         // We do this backwards, otherwise the byte indices would be off in case a
         // replacement character has a different width than the original character.
 
-        let mut fragment_start = self.width();
-        for fragment in self.fragments.iter().rev() {
+        let mut fragment_start: ColIdx = widths.iter().sum();
+        for (fragment_idx, (fragment, &width)) in
+            self.fragments.iter().zip(&widths).enumerate().rev()
+        {
             let fragment_end = fragment_start;
-            fragment_start = fragment_start.saturating_sub(fragment.rendered_width.into());
+            fragment_start = fragment_start.saturating_sub(width);
+            let is_tab = fragment.grapheme == "\t";
 
             if fragment_start > fragment_end {
                 // No processing needed if we haven't reached the visible range yet.
@@ -131,7 +199,13 @@ impl Line {
 
             // clip right if the fragment is partially visible
             if fragment_start < range.end && fragment_end > range.end {
-                result.replace(fragment.start, self.string.len(), "⋯");
+                // A tab is cut to its visible columns, other graphemes are elided
+                let replacement = if is_tab {
+                    " ".repeat(range.end.saturating_sub(fragment_start))
+                } else {
+                    "⋯".to_string()
+                };
+                result.replace(fragment.start, self.string.len(), &replacement);
                 continue;
             } else if fragment_start == range.end {
                 // Truncate right if we've reached the end of the visible range
@@ -147,22 +221,42 @@ impl Line {
                 break;
             } else if fragment_start < range.start && fragment_end > range.start {
                 // Fragment overlaps with the start of range: Remove the left side of the string
-                // and add an ellipsis
+                // and add an ellipsis, or the visible part of a tab
+                let replacement = if is_tab {
+                    " ".repeat(fragment_end.saturating_sub(range.start))
+                } else {
+                    "⋯".to_string()
+                };
                 result.replace(
                     0,
                     fragment.start.saturating_add(fragment.grapheme.len()),
-                    "⋯",
+                    &replacement,
                 );
                 // End processing since all remaining fragments will be
                 // invisible.
+                break;
             }
 
             // Fragment is fully within range: Apply replacement characters if appropriate
             if fragment_start >= range.start && fragment_end <= range.end {
-                if let Some(replacement) = fragment.replacement {
+                let replacement = guides
+                    .contains(&fragment_idx)
+                    .then(|| Self::get_indent_guide(&fragment.grapheme, width))
+                    .or_else(|| {
+                        show_whitespace
+                            .then(|| Self::get_whitespace_marker(&fragment.grapheme, width))
+                            .flatten()
+                    })
+                    // Tabs are filled up to the next tab stop
+                    .or_else(|| {
+                        fragment
+                            .replacement
+                            .map(|replacement| replacement.to_string().repeat(width))
+                    });
+                if let Some(replacement) = replacement {
                     let start_byte_idx = fragment.start;
                     let end_byte_idx = start_byte_idx.saturating_add(fragment.grapheme.len());
-                    result.replace(start_byte_idx, end_byte_idx, &replacement.to_string());
+                    result.replace(start_byte_idx, end_byte_idx, &replacement);
                 }
             }
         }
@@ -173,19 +267,41 @@ impl Line {
         self.fragments.len()
     }
 
-    pub fn width_until(&self, grapheme_idx: GraphemeIdx) -> ColIdx {
-        self.fragments
-            .iter()
-            .take(grapheme_idx)
-            .map(|fragment| match fragment.rendered_width {
-                GraphemeWidth::Half => 1,
-                GraphemeWidth::Full => 2,
-            })
-            .sum()
+    // Returns the number of columns each fragment occupies. A tab extends to the
+    // next tab stop, so its width depends on where it sits in the line.
+    fn fragment_widths(&self, tab_width: ColIdx) -> impl Iterator<Item = ColIdx> + '_ {
+        let tab_width = tab_width.max(1);
+        let mut col: ColIdx = 0;
+        self.fragments.iter().map(move |fragment| {
+            let width = if fragment.grapheme == "\t" {
+                tab_width.saturating_sub(col % tab_width)
+            } else {
+                fragment.rendered_width.into()
+            };
+            col = col.saturating_add(width);
+            width
+        })
     }
 
-    pub fn width(&self) -> ColIdx {
-        self.width_until(self.grapheme_count())
+    pub fn width_until(&self, grapheme_idx: GraphemeIdx, tab_width: ColIdx) -> ColIdx {
+        self.fragment_widths(tab_width).take(grapheme_idx).sum()
+    }
+
+    // Returns the index of the grapheme rendered at the given column, or the
+    // grapheme count if the column lies past the end of the line.
+    pub fn grapheme_idx_at(&self, col: ColIdx, tab_width: ColIdx) -> GraphemeIdx {
+        let mut width: ColIdx = 0;
+        for (grapheme_idx, fragment_width) in self.fragment_widths(tab_width).enumerate() {
+            width = width.saturating_add(fragment_width);
+            if col < width {
+                return grapheme_idx;
+            }
+        }
+        self.grapheme_count()
+    }
+
+    pub fn width(&self, tab_width: ColIdx) -> ColIdx {
+        self.width_until(self.grapheme_count(), tab_width)
     }
 
     // Inserts a character into the line, or appends it at the end if at ==
@@ -223,6 +339,15 @@ impl Line {
         self.rebuild_fragments();
     }
 
+    // Removes trailing whitespace.
+    pub fn trim_end(&mut self) {
+        let len = self.string.trim_end().len();
+        if len < self.string.len() {
+            self.string.truncate(len);
+            self.rebuild_fragments();
+        }
+    }
+
     pub fn split(&mut self, at: GraphemeIdx) -> Self {
         if let Some(fragment) = self.fragments.get(at) {
             let remainder = self.string.split_off(fragment.start);
@@ -233,6 +358,54 @@ impl Line {
         }
     }
 
+    // Returns the text between the given grapheme indices.
+    pub fn sub_str(&self, range: Range<GraphemeIdx>) -> &str {
+        let end = min(range.end, self.grapheme_count());
+        let start = min(range.start, end);
+        let start_byte_idx = self.grapheme_idx_to_byte_idx(start);
+        let end_byte_idx = self.grapheme_idx_to_byte_idx(end);
+        self.string
+            .get(start_byte_idx..end_byte_idx)
+            .unwrap_or_default()
+    }
+
+    // Returns the grapheme index of the first character which is not whitespace,
+    // or the grapheme count if the line is blank.
+    pub fn first_non_blank(&self) -> GraphemeIdx {
+        self.fragments
+            .iter()
+            .position(|fragment| !fragment.grapheme.trim().is_empty())
+            .unwrap_or_else(|| self.grapheme_count())
+    }
+
+    // Returns the grapheme ranges of all words in this line.
+    // Words end at Unicode word boundaries and wherever word characters and
+    // punctuation meet, so that `foo.bar` consists of three words. Whitespace is
+    // not part of any word, and consecutive punctuation is merged into a single
+    // word, so that `::` or `->` can be traversed in one step.
+    pub fn word_ranges(&self) -> Vec<Range<GraphemeIdx>> {
+        let segment_starts: Vec<ByteIdx> = self
+            .string
+            .split_word_bound_indices()
+            .map(|(byte_idx, _)| byte_idx)
+            .collect();
+        let mut result: Vec<Range<GraphemeIdx>> = Vec::new();
+        let mut previous_class = CharacterClass::Whitespace;
+        for (grapheme_idx, fragment) in self.fragments.iter().enumerate() {
+            let class = CharacterClass::from(fragment.grapheme.as_str());
+            let starts_segment = segment_starts.binary_search(&fragment.start).is_ok();
+            let continues_word = class == previous_class
+                && (class == CharacterClass::Punctuation || !starts_segment);
+            match result.last_mut() {
+                _ if class == CharacterClass::Whitespace => {}
+                Some(last) if continues_word => last.end = grapheme_idx.saturating_add(1),
+                _ => result.push(grapheme_idx..grapheme_idx.saturating_add(1)),
+            }
+            previous_class = class;
+        }
+        result
+    }
+
     fn byte_idx_to_grapheme_idx(&self, byte_idx: ByteIdx) -> Option<GraphemeIdx> {
         if byte_idx > self.string.len() {
             return None;
@@ -242,11 +415,14 @@ impl Line {
             .position(|fragment| fragment.start >= byte_idx)
     }
 
-    fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
+    pub fn grapheme_idx_to_byte_idx(&self, grapheme_idx: GraphemeIdx) -> ByteIdx {
         debug_assert!(grapheme_idx <= self.grapheme_count());
         if grapheme_idx == 0 || self.grapheme_count() == 0 {
             return 0;
         }
+        if grapheme_idx == self.grapheme_count() {
+            return self.string.len();
+        }
         self.fragments.get(grapheme_idx).map_or_else(
             || {
                 #[cfg(debug_assertions)]
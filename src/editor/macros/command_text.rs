@@ -0,0 +1,386 @@
+use super::super::command::{Command, Edit, Fold, Move, Select, SelectionMode, System};
+
+// Formats the command as a line of the macros file: its kind and name,
+// followed by its argument if it has one. Returns None for commands which
+// aren't recorded.
+pub fn format_command(command: &Command) -> Option<String> {
+    let text = match command {
+        Command::Move(Move::GoToLine(line_idx)) => format!("move go_to_line {line_idx}"),
+        Command::Move(Move::ToMark(name)) => format!("move to_mark {name}"),
+        Command::Move(movement) => format!("move {}", move_name(*movement)),
+        Command::Edit(Edit::Insert(character)) => format!("edit insert {character}"),
+        Command::Edit(Edit::InsertText(text)) => format!("edit insert_text {}", escape(text)),
+        Command::Edit(edit) => format!("edit {}", edit_name(edit)),
+        Command::Select(Select::Begin(mode)) => format!("select begin {}", mode_name(*mode)),
+        Command::Select(select) => format!("select {}", select_name(*select)),
+        Command::Fold(fold) => format!("fold {}", fold_name(*fold)),
+        Command::System(System::PlayMacro(register)) => format!("system play_macro {register}"),
+        Command::System(System::SetMark(name)) => format!("system set_mark {name}"),
+        Command::System(system) => format!("system {}", system_name(*system)?),
+        Command::Mouse(_) => return None,
+    };
+    Some(text)
+}
+
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    let (kind, rest) = line
+        .split_once(' ')
+        .ok_or_else(|| format!("Invalid command: {line}"))?;
+    let (name, argument) = rest
+        .split_once(' ')
+        .map_or((rest, None), |(name, argument)| (name, Some(argument)));
+    let command = match (kind, name, argument) {
+        ("move", "go_to_line", Some(line_idx)) => Command::Move(Move::GoToLine(
+            line_idx
+                .parse()
+                .map_err(|_| format!("Invalid line: {line_idx}"))?,
+        )),
+        ("move", "to_mark", Some(name)) => Command::Move(Move::ToMark(single_char(name)?)),
+        ("edit", "insert", Some(character)) => Command::Edit(Edit::Insert(single_char(character)?)),
+        ("edit", "insert_text", Some(text)) => Command::Edit(Edit::InsertText(unescape(text))),
+        ("select", "begin", Some(mode)) => Command::Select(Select::Begin(parse_mode(mode)?)),
+        ("system", "play_macro", Some(register)) => {
+            Command::System(System::PlayMacro(single_char(register)?))
+        }
+        ("system", "set_mark", Some(name)) => Command::System(System::SetMark(single_char(name)?)),
+        ("move", name, None) => Command::Move(parse_move(name)?),
+        ("edit", name, None) => Command::Edit(parse_edit(name)?),
+        ("select", name, None) => Command::Select(parse_select(name)?),
+        ("fold", name, None) => Command::Fold(parse_fold(name)?),
+        ("system", name, None) => Command::System(parse_system(name)?),
+        _ => return Err(format!("Invalid command: {line}")),
+    };
+    Ok(command)
+}
+
+// region: names
+
+const fn move_name(movement: Move) -> &'static str {
+    match movement {
+        Move::PageUp => "page_up",
+        Move::PageDown => "page_down",
+        Move::StartOfLine => "start_of_line",
+        Move::EndOfLine => "end_of_line",
+        Move::Up => "up",
+        Move::Left => "left",
+        Move::Right => "right",
+        Move::Down => "down",
+        Move::FirstNonBlank => "first_non_blank",
+        Move::WordForward => "word_forward",
+        Move::WordBackward => "word_backward",
+        Move::WordEnd => "word_end",
+        Move::StartOfDocument => "start_of_document",
+        Move::EndOfDocument => "end_of_document",
+        Move::GoToLine(_) => "go_to_line",
+        Move::MatchingBracket => "matching_bracket",
+        Move::ToMark(_) => "to_mark",
+        Move::JumpBack => "jump_back",
+        Move::JumpForward => "jump_forward",
+    }
+}
+
+fn parse_move(name: &str) -> Result<Move, String> {
+    let movement = match name {
+        "page_up" => Move::PageUp,
+        "page_down" => Move::PageDown,
+        "start_of_line" => Move::StartOfLine,
+        "end_of_line" => Move::EndOfLine,
+        "up" => Move::Up,
+        "left" => Move::Left,
+        "right" => Move::Right,
+        "down" => Move::Down,
+        "first_non_blank" => Move::FirstNonBlank,
+        "word_forward" => Move::WordForward,
+        "word_backward" => Move::WordBackward,
+        "word_end" => Move::WordEnd,
+        "start_of_document" => Move::StartOfDocument,
+        "end_of_document" => Move::EndOfDocument,
+        "matching_bracket" => Move::MatchingBracket,
+        "jump_back" => Move::JumpBack,
+        "jump_forward" => Move::JumpForward,
+        _ => return Err(format!("Unknown move command: {name}")),
+    };
+    Ok(movement)
+}
+
+const fn edit_name(edit: &Edit) -> &'static str {
+    match edit {
+        Edit::Insert(_) => "insert",
+        Edit::InsertText(_) => "insert_text",
+        Edit::InsertNewLine => "insert_new_line",
+        Edit::Indent => "indent",
+        Edit::Dedent => "dedent",
+        Edit::Delete => "delete",
+        Edit::DeleteBackward => "delete_backward",
+        Edit::DeleteWordForward => "delete_word_forward",
+        Edit::DeleteWordBackward => "delete_word_backward",
+        Edit::DeleteLines => "delete_lines",
+        Edit::DuplicateLines => "duplicate_lines",
+        Edit::MoveLinesUp => "move_lines_up",
+        Edit::MoveLinesDown => "move_lines_down",
+        Edit::JoinLines => "join_lines",
+        Edit::SortLines => "sort_lines",
+        Edit::ToggleComment => "toggle_comment",
+        Edit::Cut => "cut",
+        Edit::CutAppend => "cut_append",
+        Edit::Copy => "copy",
+        Edit::Paste => "paste",
+        Edit::PasteAfter => "paste_after",
+        Edit::CyclePaste => "cycle_paste",
+    }
+}
+
+fn parse_edit(name: &str) -> Result<Edit, String> {
+    let edit = match name {
+        "insert_new_line" => Edit::InsertNewLine,
+        "indent" => Edit::Indent,
+        "dedent" => Edit::Dedent,
+        "delete" => Edit::Delete,
+        "delete_backward" => Edit::DeleteBackward,
+        "delete_word_forward" => Edit::DeleteWordForward,
+        "delete_word_backward" => Edit::DeleteWordBackward,
+        "delete_lines" => Edit::DeleteLines,
+        "duplicate_lines" => Edit::DuplicateLines,
+        "move_lines_up" => Edit::MoveLinesUp,
+        "move_lines_down" => Edit::MoveLinesDown,
+        "join_lines" => Edit::JoinLines,
+        "sort_lines" => Edit::SortLines,
+        "toggle_comment" => Edit::ToggleComment,
+        "cut" => Edit::Cut,
+        "cut_append" => Edit::CutAppend,
+        "copy" => Edit::Copy,
+        "paste" => Edit::Paste,
+        "paste_after" => Edit::PasteAfter,
+        "cycle_paste" => Edit::CyclePaste,
+        _ => return Err(format!("Unknown edit command: {name}")),
+    };
+    Ok(edit)
+}
+
+const fn mode_name(mode: SelectionMode) -> &'static str {
+    match mode {
+        SelectionMode::Character => "character",
+        SelectionMode::Inclusive => "inclusive",
+        SelectionMode::Line => "line",
+        SelectionMode::Block => "block",
+        SelectionMode::InclusiveBlock => "inclusive_block",
+    }
+}
+
+fn parse_mode(name: &str) -> Result<SelectionMode, String> {
+    let mode = match name {
+        "character" => SelectionMode::Character,
+        "inclusive" => SelectionMode::Inclusive,
+        "line" => SelectionMode::Line,
+        "block" => SelectionMode::Block,
+        "inclusive_block" => SelectionMode::InclusiveBlock,
+        _ => return Err(format!("Unknown selection mode: {name}")),
+    };
+    Ok(mode)
+}
+
+const fn select_name(select: Select) -> &'static str {
+    match select {
+        Select::Begin(_) => "begin",
+        Select::Clear => "clear",
+        Select::CollapseToStart => "collapse_to_start",
+        Select::AddCaretAbove => "add_caret_above",
+        Select::AddCaretBelow => "add_caret_below",
+        Select::AddCaretAtNextOccurrence => "add_caret_at_next_occurrence",
+    }
+}
+
+fn parse_select(name: &str) -> Result<Select, String> {
+    let select = match name {
+        "clear" => Select::Clear,
+        "collapse_to_start" => Select::CollapseToStart,
+        "add_caret_above" => Select::AddCaretAbove,
+        "add_caret_below" => Select::AddCaretBelow,
+        "add_caret_at_next_occurrence" => Select::AddCaretAtNextOccurrence,
+        _ => return Err(format!("Unknown select command: {name}")),
+    };
+    Ok(select)
+}
+
+const fn fold_name(fold: Fold) -> &'static str {
+    match fold {
+        Fold::Close => "close",
+        Fold::Open => "open",
+        Fold::Toggle => "toggle",
+        Fold::CloseAll => "close_all",
+        Fold::OpenAll => "open_all",
+    }
+}
+
+fn parse_fold(name: &str) -> Result<Fold, String> {
+    let fold = match name {
+        "close" => Fold::Close,
+        "open" => Fold::Open,
+        "toggle" => Fold::Toggle,
+        "close_all" => Fold::CloseAll,
+        "open_all" => Fold::OpenAll,
+        _ => return Err(format!("Unknown fold command: {name}")),
+    };
+    Ok(fold)
+}
+
+// Returns None for system commands which aren't recorded.
+const fn system_name(system: System) -> Option<&'static str> {
+    let name = match system {
+        System::Save => "save",
+        System::Quit => "quit",
+        System::Dismiss => "dismiss",
+        System::Search => "search",
+        System::SearchBackward => "search_backward",
+        System::CommandLine => "command_line",
+        System::SwitchTheme => "switch_theme",
+        System::ShowFileSettings => "show_file_settings",
+        System::ToggleWhitespace => "toggle_whitespace",
+        System::PlayMacro(_) => "play_macro",
+        System::SetMark(_) => "set_mark",
+        System::Resize(_) | System::RecordMacro(_) | System::StopMacro => return None,
+    };
+    Some(name)
+}
+
+fn parse_system(name: &str) -> Result<System, String> {
+    let system = match name {
+        "save" => System::Save,
+        "quit" => System::Quit,
+        "dismiss" => System::Dismiss,
+        "search" => System::Search,
+        "search_backward" => System::SearchBackward,
+        "command_line" => System::CommandLine,
+        "switch_theme" => System::SwitchTheme,
+        "show_file_settings" => System::ShowFileSettings,
+        "toggle_whitespace" => System::ToggleWhitespace,
+        _ => return Err(format!("Unknown system command: {name}")),
+    };
+    Ok(system)
+}
+
+// region end
+
+// region: arguments
+
+fn single_char(argument: &str) -> Result<char, String> {
+    let mut chars = argument.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => Ok(character),
+        _ => Err(format!("Expected a single character, got: {argument}")),
+    }
+}
+
+// Escapes backslashes and line breaks, so that the text fits on one line.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(character) = chars.next() {
+        if character != '\\' {
+            result.push(character);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// region end
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    // Formats the command, parses the result and formats it again.
+    fn round_trip(command: &Command) -> (String, String) {
+        let text = format_command(command).unwrap_or_default();
+        let parsed = parse_command(&text).map_or_else(
+            |err| err,
+            |command| format_command(&command).unwrap_or_default(),
+        );
+        (text, parsed)
+    }
+
+    #[test]
+    fn commands_survive_a_round_trip() {
+        let commands = [
+            Command::Move(Move::WordForward),
+            Command::Move(Move::GoToLine(42)),
+            Command::Move(Move::ToMark('a')),
+            Command::Edit(Edit::DeleteWordBackward),
+            Command::Edit(Edit::Insert('é')),
+            Command::Select(Select::Begin(SelectionMode::InclusiveBlock)),
+            Command::Select(Select::AddCaretAtNextOccurrence),
+            Command::Fold(Fold::CloseAll),
+            Command::System(System::PlayMacro('q')),
+            Command::System(System::SetMark('b')),
+            Command::System(System::ToggleWhitespace),
+        ];
+        for command in &commands {
+            let (text, parsed) = round_trip(command);
+            assert_eq!(parsed, text);
+        }
+    }
+
+    #[test]
+    fn inserted_spaces_survive_a_round_trip() {
+        let (text, parsed) = round_trip(&Command::Edit(Edit::Insert(' ')));
+        assert_eq!(text, "edit insert  ");
+        assert_eq!(parsed, text);
+    }
+
+    #[test]
+    fn inserted_text_is_escaped_onto_one_line() {
+        let inserted = "a \\n\\\n\r\nb ";
+        let command = Command::Edit(Edit::InsertText(inserted.to_string()));
+        let (text, parsed) = round_trip(&command);
+        assert_eq!(text, "edit insert_text a \\\\n\\\\\\n\\r\\nb ");
+        assert_eq!(parsed, text);
+        assert_eq!(text.lines().count(), 1);
+        let Ok(Command::Edit(Edit::InsertText(unescaped))) = parse_command(&text) else {
+            panic!("Expected inserted text: {text}");
+        };
+        assert_eq!(unescaped, inserted);
+    }
+
+    #[test]
+    fn transient_commands_are_not_recorded() {
+        assert_eq!(
+            format_command(&Command::System(System::RecordMacro('q'))),
+            None
+        );
+        assert_eq!(format_command(&Command::System(System::StopMacro)), None);
+        assert_eq!(
+            format_command(&Command::System(System::Resize(Size::default()))),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_commands_are_rejected() {
+        for line in [
+            "move",
+            "move sideways",
+            "move go_to_line x",
+            "edit insert ab",
+            "edit delete now",
+            "select begin diagonal",
+            "system play_macro",
+            "undo",
+        ] {
+            assert!(parse_command(line).is_err(), "{line}");
+        }
+    }
+}
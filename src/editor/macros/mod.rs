@@ -0,0 +1,170 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs::{create_dir_all, read_to_string, write},
+    io::ErrorKind,
+    path::PathBuf,
+};
+
+use super::{
+    command::{Command, System},
+    config,
+};
+
+mod command_text;
+
+use command_text::{format_command, parse_command};
+
+const FILE_NAME: &str = "macros";
+
+// Recorded sequences of commands, stored in registers named by a single
+// character.
+#[derive(Default)]
+pub struct Macros {
+    registers: BTreeMap<char, Vec<Command>>,
+    // The register being recorded into, and the commands recorded so far.
+    recording: Option<(char, Vec<Command>)>,
+    // The registers being played, innermost last. Commands aren't recorded
+    // while a macro plays, as the macro itself is recorded instead.
+    playing: Vec<char>,
+    // Whether macros are written to the configuration directory whenever a
+    // recording finishes.
+    save: bool,
+}
+
+impl Macros {
+    // Loads the macros saved in the configuration directory, if any.
+    pub fn load(save: bool) -> Result<Self, String> {
+        let mut macros = Self {
+            save,
+            ..Self::default()
+        };
+        let Some(path) = path() else {
+            return Ok(macros);
+        };
+        match read_to_string(&path) {
+            Ok(contents) => macros.registers = parse(&contents)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("Could not read {}: {err}", path.display())),
+        }
+        Ok(macros)
+    }
+
+    pub const fn recording_register(&self) -> Option<char> {
+        match &self.recording {
+            Some((register, _)) => Some(*register),
+            None => None,
+        }
+    }
+
+    // Starts recording into the register, finishing any recording in progress.
+    pub fn start_recording(&mut self, register: char) -> Result<(), String> {
+        self.stop_recording()?;
+        self.recording = Some((register, Vec::new()));
+        Ok(())
+    }
+
+    // Stores the recorded commands in their register, returning the register
+    // and the number of commands recorded.
+    pub fn stop_recording(&mut self) -> Result<Option<(char, usize)>, String> {
+        let Some((register, commands)) = self.recording.take() else {
+            return Ok(None);
+        };
+        let len = commands.len();
+        self.registers.insert(register, commands);
+        if self.save {
+            self.write()?;
+        }
+        Ok(Some((register, len)))
+    }
+
+    pub fn record(&mut self, command: &Command) {
+        if !self.playing.is_empty() || !is_recordable(command) {
+            return;
+        }
+        if let Some((_, commands)) = &mut self.recording {
+            commands.push(command.clone());
+        }
+    }
+
+    // Returns the commands to play for the register, or None if the register is
+    // empty or already playing, as a macro invoking itself would never end.
+    // Every successful call must be followed by `end_playback`.
+    pub fn begin_playback(&mut self, register: char) -> Option<Vec<Command>> {
+        if self.playing.contains(&register) {
+            return None;
+        }
+        let commands = self.registers.get(&register)?.clone();
+        self.playing.push(register);
+        Some(commands)
+    }
+
+    pub fn end_playback(&mut self) {
+        self.playing.pop();
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let Some(path) = path() else {
+            return Err("Could not determine the configuration directory".to_string());
+        };
+        let mut contents = String::new();
+        for (register, commands) in &self.registers {
+            let _ = writeln!(contents, "[{register}]");
+            for command in commands.iter().filter_map(format_command) {
+                let _ = writeln!(contents, "{command}");
+            }
+        }
+        path.parent()
+            .map_or(Ok(()), create_dir_all)
+            .and_then(|()| write(&path, contents))
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))
+    }
+}
+
+// Mouse input isn't recorded, as it depends on the screen layout at the time.
+const fn is_recordable(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::Mouse(_)
+            | Command::System(System::Resize(_) | System::RecordMacro(_) | System::StopMacro)
+    )
+}
+
+fn path() -> Option<PathBuf> {
+    config::directory().map(|directory| directory.join(FILE_NAME))
+}
+
+// Parses the macros file, in which each macro starts with a `[register]`
+// line, followed by one command per line. Empty lines and lines starting with
+// `#` are ignored.
+fn parse(contents: &str) -> Result<BTreeMap<char, Vec<Command>>, String> {
+    let mut registers = BTreeMap::new();
+    let mut current = None;
+    for (idx, line) in contents.lines().enumerate() {
+        let line_number = idx.saturating_add(1);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let mut chars = name.chars();
+            let (Some(register), None) = (chars.next(), chars.next()) else {
+                return Err(format!(
+                    "Macros line {line_number}: invalid register: {name}"
+                ));
+            };
+            registers.insert(register, Vec::new());
+            current = Some(register);
+            continue;
+        }
+        let commands = current
+            .and_then(|register| registers.get_mut(&register))
+            .ok_or_else(|| format!("Macros line {line_number}: expected `[register]`"))?;
+        let command =
+            parse_command(line).map_err(|err| format!("Macros line {line_number}: {err}"))?;
+        commands.push(command);
+    }
+    Ok(registers)
+}
@@ -1,6 +1,7 @@
 use std::{
     env,
     io::Error,
+    ops::Range,
     panic::{set_hook, take_hook},
 };
 
@@ -11,27 +12,47 @@ use crate::prelude::*;
 mod annotated_string;
 mod annotation;
 pub mod annotation_type;
+mod clipboard;
 mod command;
+mod config;
 mod document_status;
+mod emacs;
 mod file_type;
+mod indentation;
+mod language;
 mod line;
+mod macros;
 mod terminal;
+mod theme;
 mod ui_components;
+mod vim;
 
 use annotated_string::AnnotatedString;
 use annotation::Annotation;
 pub use annotation_type::AnnotationType;
+use clipboard::{Clipboard, ClipboardEntry};
+use config::{Config, KeyBindings};
 use document_status::DocumentStatus;
+use emacs::Emacs;
 use file_type::FileType;
+use indentation::Indentation;
+use language::Languages;
 use line::Line;
-use terminal::Terminal;
+use macros::Macros;
+use terminal::{CaretStyle, Terminal};
+use theme::{DEFAULT_THEME, Theme, ThemeElement, Themes};
 use ui_components::{CommandBar, MessageBar, StatusBar, UIComponent, View};
+use vim::{ExCommand, Vim};
 
 use self::command::{
-    Command::{self, Edit, Move, System},
-    Edit::InsertNewLine,
-    Move::{Down, Left, Right, Up},
-    System::{Dismiss, Quit, Resize, Save, Search},
+    Command::{self, Edit, Fold, Mouse, Move, Select, System},
+    Edit::{Copy, Cut, CutAppend, CyclePaste, InsertNewLine, Paste, PasteAfter},
+    Move::{Down, GoToLine, Left, Right, Up},
+    Select::Clear,
+    System::{
+        CommandLine, Dismiss, PlayMacro, Quit, RecordMacro, Resize, Save, Search, SearchBackward,
+        SetMark, ShowFileSettings, StopMacro, SwitchTheme, ToggleWhitespace,
+    },
 };
 
 const QUIT_TIMES: u8 = 3;
@@ -40,6 +61,8 @@ const QUIT_TIMES: u8 = 3;
 enum PromptType {
     Search,
     Save,
+    CommandLine,
+    Theme,
     #[default]
     None,
 }
@@ -61,6 +84,15 @@ pub struct Editor {
     prompt_type: PromptType,
     terminal_size: Size,
     quit_times: u8,
+    clipboard: Clipboard,
+    // The range of the text pasted by the previous command, which can be
+    // replaced by older clipboard entries.
+    last_paste: Option<Range<Location>>,
+    vim: Option<Vim>,
+    emacs: Option<Emacs>,
+    themes: Themes,
+    theme: Theme,
+    macros: Macros,
 }
 
 impl Editor {
@@ -76,9 +108,49 @@ impl Editor {
         let mut editor = Self::default();
         let size = Terminal::size().unwrap_or_default();
         editor.handle_resize_command(size);
-        editor.update_message("HELP: Ctrl-F = find | Ctrl-S = save | Ctrl-Q = quit");
-        let args: Vec<String> = env::args().collect();
-        if let Some(file_name) = args.get(1) {
+        let config = Config::load(env::args().skip(1));
+        let config = match config {
+            Ok(config) => {
+                editor.update_message(Self::help_message(config.key_bindings));
+                config
+            }
+            Err(err) => {
+                editor.update_message(&format!("ERR: {err}"));
+                Config::default()
+            }
+        };
+        let (themes, errors) = Themes::load();
+        editor.themes = themes;
+        if let Some(err) = errors.first() {
+            editor.update_message(&format!("ERR: {err}"));
+        }
+        if let Some(err) = Languages::initialize().first() {
+            editor.update_message(&format!("ERR: {err}"));
+        }
+        match Macros::load(config.save_macros) {
+            Ok(macros) => editor.macros = macros,
+            Err(err) => editor.update_message(&format!("ERR: {err}")),
+        }
+        editor.switch_theme(DEFAULT_THEME);
+        if let Some(theme) = &config.theme {
+            editor.switch_theme(theme);
+        }
+        editor.view.set_indentation(config.indentation);
+        editor.view.set_show_whitespace(config.show_whitespace);
+        editor
+            .view
+            .set_strip_trailing_whitespace(config.strip_trailing_whitespace);
+        editor.view.set_rulers(config.rulers);
+        editor.view.set_indent_guides(config.indent_guides);
+        if !config.mouse {
+            let _ = Terminal::disable_mouse_capture();
+        }
+        match config.key_bindings {
+            KeyBindings::Default => {}
+            KeyBindings::Vim => editor.vim = Some(Vim::default()),
+            KeyBindings::Emacs => editor.emacs = Some(Emacs::default()),
+        }
+        if let Some(file_name) = &config.file_name {
             debug_assert!(!file_name.is_empty());
             if editor.view.load(file_name).is_err() {
                 editor.update_message(&format!("ERR: Could not open file: {file_name}"));
@@ -88,6 +160,16 @@ impl Editor {
         Ok(editor)
     }
 
+    const fn help_message(key_bindings: KeyBindings) -> &'static str {
+        match key_bindings {
+            KeyBindings::Default => {
+                "HELP: Ctrl-F = find | Ctrl-S = save | Ctrl-T = theme | Ctrl-Q = quit"
+            }
+            KeyBindings::Vim => "HELP: i = insert | Esc = normal | :w = save | :q = quit",
+            KeyBindings::Emacs => "HELP: C-s = search | C-x C-s = save | C-x C-c = quit",
+        }
+    }
+
     // region end
 
     // region: Event Loop
@@ -122,35 +204,49 @@ impl Editor {
         let bottom_bar_row = self.terminal_size.height.saturating_sub(1);
         let _ = Terminal::hide_caret();
         if self.in_prompt() {
-            self.command_bar.render(bottom_bar_row);
+            self.command_bar.render(bottom_bar_row, &self.theme);
         } else {
-            self.message_bar.render(bottom_bar_row);
+            self.message_bar.render(bottom_bar_row, &self.theme);
         }
         if self.terminal_size.height > 1 {
             self.status_bar
-                .render(self.terminal_size.height.saturating_sub(1));
+                .render(self.terminal_size.height.saturating_sub(1), &self.theme);
         }
         if self.terminal_size.height > 2 {
-            self.view.render(0);
+            self.view.render(0, &self.theme);
         }
         let new_caret_pos = if self.in_prompt() {
-            Position {
+            Some(Position {
                 row: bottom_bar_row,
                 col: self.command_bar.caret_position_col(),
-            }
+            })
         } else {
             self.view.caret_position()
         };
-        debug_assert!(new_caret_pos.col <= self.terminal_size.width);
-        debug_assert!(new_caret_pos.row <= self.terminal_size.height);
-
-        let _ = Terminal::move_caret_to(new_caret_pos);
-        let _ = Terminal::show_caret();
+        // The caret stays hidden while it is scrolled out of view
+        if let Some(new_caret_pos) = new_caret_pos {
+            debug_assert!(new_caret_pos.col <= self.terminal_size.width);
+            debug_assert!(new_caret_pos.row <= self.terminal_size.height);
+
+            let _ = Terminal::move_caret_to(new_caret_pos);
+            let _ = Terminal::set_caret_style(self.caret_style());
+            let _ = Terminal::show_caret();
+        }
         let _ = Terminal::execute();
     }
 
+    fn caret_style(&self) -> CaretStyle {
+        match &self.vim {
+            Some(_) if self.in_prompt() => CaretStyle::Bar,
+            Some(vim) => vim.mode().caret_style(),
+            None => CaretStyle::Default,
+        }
+    }
+
     fn refresh_status(&mut self) {
-        let status = self.view.get_status();
+        let mut status = self.view.get_status();
+        status.mode = self.vim.as_ref().map(Vim::mode);
+        status.recording = self.macros.recording_register();
         let title = format!("{} - {NAME}", status.file_name);
         self.status_bar.update_status(status);
         if title != self.title && matches!(Terminal::set_title(&title), Ok(())) {
@@ -161,13 +257,47 @@ impl Editor {
     fn evaluate_event(&mut self, event: Event) {
         let should_process = match &event {
             Event::Key(KeyEvent { kind, .. }) => kind == &KeyEventKind::Press,
-            Event::Resize(..) => true,
+            Event::Resize(..) | Event::Mouse(..) | Event::Paste(..) => true,
             _ => false,
         };
-        if should_process {
-            if let Ok(command) = Command::try_from(event) {
+        if !should_process {
+            return;
+        }
+        if let Event::Key(key_event) = event {
+            if self.vim.is_some() && !self.in_prompt() {
+                self.evaluate_vim_key_event(key_event);
+                return;
+            }
+            if let Some(emacs) = &mut self.emacs {
+                for command in emacs.handle_key_event(key_event, &self.view) {
+                    self.process_command(command);
+                }
+                return;
+            }
+        }
+        if let Ok(command) = Command::try_from(event) {
+            self.process_command(command);
+        }
+    }
+
+    // Feeds the key event to vim, followed by any keys it replays for `.`
+    fn evaluate_vim_key_event(&mut self, key_event: KeyEvent) {
+        let mut next_key = Some(key_event);
+        while let Some(key_event) = next_key {
+            let Some(vim) = &mut self.vim else {
+                return;
+            };
+            for command in vim.handle_key_event(key_event, &self.view) {
+                self.process_command(command);
+            }
+            if let Some(command) = self
+                .vim
+                .as_ref()
+                .and_then(|vim| vim.caret_correction(&self.view))
+            {
                 self.process_command(command);
             }
+            next_key = self.vim.as_mut().and_then(Vim::next_replayed_key);
         }
     }
 
@@ -180,9 +310,12 @@ impl Editor {
             self.handle_resize_command(size);
             return;
         }
+        self.macros.record(&command);
         match self.prompt_type {
             PromptType::Search => self.process_command_during_search(command),
             PromptType::Save => self.process_command_during_save(command),
+            PromptType::CommandLine => self.process_command_during_command_line(command),
+            PromptType::Theme => self.process_command_during_theme(command),
             PromptType::None => self.process_command_no_prompt(command),
         }
     }
@@ -194,17 +327,114 @@ impl Editor {
         }
         self.reset_quit_times();
 
+        let last_paste = self.last_paste.take();
         match command {
-            System(Quit | Resize(_) | Dismiss) => {}
-            System(Search) => self.set_prompt(PromptType::Search),
+            System(Quit | Resize(_)) => {}
+            System(Dismiss) => {
+                self.view.clear_secondary_carets();
+                self.view.handle_select_command(Clear);
+            }
+            System(Search | SearchBackward) => self.set_prompt(PromptType::Search),
             System(Save) => self.handle_save_command(),
+            System(CommandLine) => self.set_prompt(PromptType::CommandLine),
+            System(SwitchTheme) => self.set_prompt(PromptType::Theme),
+            System(ShowFileSettings) => self.show_file_settings(),
+            System(ToggleWhitespace) => {
+                let shown = self.view.toggle_whitespace();
+                self.update_message(if shown {
+                    "Showing whitespace"
+                } else {
+                    "Hiding whitespace"
+                });
+            }
+            System(RecordMacro(register)) => self.start_macro_recording(register),
+            System(StopMacro) => self.stop_macro_recording(),
+            System(PlayMacro(register)) => self.play_macro(register),
+            System(SetMark(name)) => {
+                self.view.set_mark(name);
+                self.update_message(&format!("Mark {name} set"));
+            }
+            Edit(Copy) => {
+                if let Some(entry) = self.view.copy_selection() {
+                    self.clipboard.set(entry);
+                }
+            }
+            Edit(Cut) => {
+                if let Some(entry) = self.view.cut_selection() {
+                    self.clipboard.set(entry);
+                }
+            }
+            Edit(CutAppend) => {
+                if let Some(entry) = self.view.cut_selection() {
+                    self.clipboard.append(entry);
+                }
+            }
+            Edit(Paste | PasteAfter) => {
+                if let Some(entry) = self.clipboard.get() {
+                    let after = matches!(command, Edit(PasteAfter));
+                    self.last_paste = Some(self.view.paste(entry, after));
+                }
+            }
+            Edit(CyclePaste) => {
+                if let Some(range) = last_paste {
+                    self.clipboard.rotate();
+                    if let Some(entry) = self.clipboard.get() {
+                        self.last_paste = Some(self.view.replace_pasted(range, entry));
+                    }
+                } else {
+                    self.update_message("Previous command was not a paste");
+                }
+            }
             Edit(edit_command) => self.view.handle_edit_command(edit_command),
             Move(move_command) => self.view.handle_move_command(move_command),
+            Select(select_command) => self.view.handle_select_command(select_command),
+            Fold(fold_command) => self.view.handle_fold_command(fold_command),
+            Mouse(mouse_command) => {
+                self.view.handle_mouse_command(mouse_command);
+                if let Some(vim) = &mut self.vim {
+                    vim.sync_selection(self.view.has_selection());
+                }
+            }
         }
     }
 
     // region end
 
+    // region: macros
+
+    fn start_macro_recording(&mut self, register: char) {
+        match self.macros.start_recording(register) {
+            Ok(()) => self.update_message(&format!("Recording @{register}")),
+            Err(err) => self.update_message(&format!("ERR: {err}")),
+        }
+    }
+
+    fn stop_macro_recording(&mut self) {
+        match self.macros.stop_recording() {
+            Ok(Some((register, len))) => {
+                self.update_message(&format!("Recorded {len} commands to @{register}"));
+            }
+            Ok(None) => self.update_message("Not recording a macro"),
+            Err(err) => self.update_message(&format!("ERR: {err}")),
+        }
+    }
+
+    fn play_macro(&mut self, register: char) {
+        let Some(commands) = self.macros.begin_playback(register) else {
+            self.update_message(&format!("Nothing to play in @{register}"));
+            return;
+        };
+        for command in commands {
+            self.process_command(command);
+            if let Some(vim) = &mut self.vim {
+                vim.sync_selection(self.view.has_selection());
+            }
+        }
+        self.macros.end_playback();
+    }
+
+    // region end
+
     // region: resize command handling
 
     fn handle_resize_command(&mut self, size: Size) {
@@ -233,8 +463,13 @@ impl Editor {
         if !self.view.get_status().is_modified || self.quit_times + 1 == QUIT_TIMES {
             self.should_quit = true;
         } else if self.view.get_status().is_modified {
+            let quit_key = if self.emacs.is_some() {
+                "C-x C-c"
+            } else {
+                "Ctrl-Q"
+            };
             self.update_message(&format!(
-                "WARNING! File has unsaved changes. Press Ctrl-Q {} more times to quit.",
+                "WARNING! File has unsaved changes. Press {quit_key} {} more times to quit.",
                 QUIT_TIMES - self.quit_times - 1
             ));
             self.quit_times += 1;
@@ -262,7 +497,15 @@ impl Editor {
 
     fn process_command_during_save(&mut self, command: Command) {
         match command {
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {}
+            System(
+                Quit | Resize(_) | Search | SearchBackward | Save | CommandLine | SwitchTheme
+                | ShowFileSettings | RecordMacro(_) | StopMacro | PlayMacro(_) | SetMark(_)
+                | ToggleWhitespace,
+            )
+            | Move(_)
+            | Select(_)
+            | Fold(_)
+            | Mouse(_) => {}
             System(Dismiss) => {
                 self.set_prompt(PromptType::None);
                 self.update_message("Save aborted.");
@@ -276,7 +519,7 @@ impl Editor {
         }
     }
 
-    fn save(&mut self, file_name: Option<&str>) {
+    fn save(&mut self, file_name: Option<&str>) -> bool {
         let result = if let Some(name) = file_name {
             self.view.save_as(name)
         } else {
@@ -287,6 +530,7 @@ impl Editor {
         } else {
             self.message_bar.update_message("Error writing file!");
         }
+        result.is_ok()
     }
 
     // region end
@@ -308,12 +552,123 @@ impl Editor {
                 let query = self.command_bar.value();
                 self.view.search(&query);
             }
-            Move(Down | Right) => self.view.search_next(),
-            Move(Left | Up) => self.view.search_prev(),
-            System(Quit | Resize(_) | Search | Save) | Move(_) => {}
+            Move(Down | Right) | System(Search) => self.view.search_next(),
+            Move(Left | Up) | System(SearchBackward) => self.view.search_prev(),
+            System(
+                Quit | Resize(_) | Save | CommandLine | SwitchTheme | ShowFileSettings
+                | RecordMacro(_) | StopMacro | PlayMacro(_) | SetMark(_) | ToggleWhitespace,
+            )
+            | Move(_)
+            | Select(_)
+            | Fold(_)
+            | Mouse(_) => {}
+        }
+    }
+
+    // region end
+
+    // region: command line prompt handling
+
+    fn process_command_during_command_line(&mut self, command: Command) {
+        match command {
+            System(
+                Quit | Resize(_) | Search | SearchBackward | Save | CommandLine | SwitchTheme
+                | ShowFileSettings | RecordMacro(_) | StopMacro | PlayMacro(_) | SetMark(_)
+                | ToggleWhitespace,
+            )
+            | Move(_)
+            | Select(_)
+            | Fold(_)
+            | Mouse(_) => {}
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewLine) => {
+                let input = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                match ExCommand::try_from(input.as_str()) {
+                    Ok(ex_command) => self.execute_ex_command(ex_command),
+                    Err(err) => self.update_message(&format!("ERR: {err}")),
+                }
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
+        }
+    }
+
+    fn execute_ex_command(&mut self, ex_command: ExCommand) {
+        match ex_command {
+            ExCommand::Write(file_name) => {
+                if file_name.is_some() || self.view.is_file_loaded() {
+                    self.save(file_name.as_deref());
+                } else {
+                    self.update_message("ERR: No file name");
+                }
+            }
+            ExCommand::Quit => {
+                if self.view.get_status().is_modified {
+                    self.update_message("ERR: No write since last change (add ! to override)");
+                } else {
+                    self.should_quit = true;
+                }
+            }
+            ExCommand::ForceQuit => self.should_quit = true,
+            ExCommand::WriteQuit => {
+                if !self.view.is_file_loaded() {
+                    self.update_message("ERR: No file name");
+                } else if self.save(None) {
+                    self.should_quit = true;
+                }
+            }
+            ExCommand::GoToLine(line_idx) => {
+                self.view.handle_move_command(GoToLine(line_idx));
+            }
+            ExCommand::Theme(Some(name)) => self.switch_theme(&name),
+            ExCommand::ShowFileSettings => self.show_file_settings(),
+            ExCommand::ShowWhitespace(show_whitespace) => {
+                self.view.set_show_whitespace(show_whitespace);
+            }
+            ExCommand::Theme(None) => {
+                let message = self.theme.name().to_string();
+                self.update_message(&message);
+            }
+        }
+    }
+
+    // region end
+
+    // region: theme prompt handling
+
+    fn process_command_during_theme(&mut self, command: Command) {
+        match command {
+            System(
+                Quit | Resize(_) | Search | SearchBackward | Save | CommandLine | SwitchTheme
+                | ShowFileSettings | RecordMacro(_) | StopMacro | PlayMacro(_) | SetMark(_)
+                | ToggleWhitespace,
+            )
+            | Move(_)
+            | Select(_)
+            | Fold(_)
+            | Mouse(_) => {}
+            System(Dismiss) => self.set_prompt(PromptType::None),
+            Edit(InsertNewLine) => {
+                let name = self.command_bar.value();
+                self.set_prompt(PromptType::None);
+                self.switch_theme(name.trim());
+            }
+            Edit(edit_command) => self.command_bar.handle_edit_command(edit_command),
         }
     }
 
+    fn switch_theme(&mut self, name: &str) {
+        let Some(theme) = self.themes.get(name) else {
+            self.update_message(&format!("ERR: Unknown theme: {name}"));
+            return;
+        };
+        self.theme = theme.clone();
+        self.view.set_needs_redraw(true);
+        self.status_bar.set_needs_redraw(true);
+        self.message_bar.set_needs_redraw(true);
+        self.command_bar.set_needs_redraw(true);
+    }
+
     // region end
 
     // region: message & command bar
@@ -322,6 +677,11 @@ impl Editor {
         self.message_bar.update_message(new_message);
     }
 
+    fn show_file_settings(&mut self) {
+        let file_settings = self.view.file_settings();
+        self.update_message(&file_settings);
+    }
+
     // region end
 
     // region: prompt handling
@@ -334,6 +694,11 @@ impl Editor {
         match prompt_type {
             PromptType::None => self.message_bar.set_needs_redraw(true),
             PromptType::Save => self.command_bar.set_prompt("Save as: "),
+            PromptType::CommandLine => self.command_bar.set_prompt(":"),
+            PromptType::Theme => {
+                let prompt = format!("Theme ({}): ", self.themes.names().join(", "));
+                self.command_bar.set_prompt(&prompt);
+            }
             PromptType::Search => {
                 self.view.enter_search();
                 self.command_bar
@@ -0,0 +1,21 @@
+use crossterm::cursor::SetCursorStyle;
+
+#[derive(Copy, Clone, Default, Eq, PartialEq)]
+pub enum CaretStyle {
+    #[default]
+    Default,
+    Block,
+    Bar,
+    Underline,
+}
+
+impl From<CaretStyle> for SetCursorStyle {
+    fn from(caret_style: CaretStyle) -> Self {
+        match caret_style {
+            CaretStyle::Default => Self::DefaultUserShape,
+            CaretStyle::Block => Self::SteadyBlock,
+            CaretStyle::Bar => Self::SteadyBar,
+            CaretStyle::Underline => Self::SteadyUnderScore,
+        }
+    }
+}
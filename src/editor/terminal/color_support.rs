@@ -0,0 +1,223 @@
+use std::env;
+
+use crossterm::style::Color;
+
+// The 16 standard colours, in palette order, with their usual xterm values.
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// The channel values used by the 6x6x6 colour cube of the 256-colour palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+const CUBE_START: u8 = 16;
+const GRAY_START: u8 = 232;
+
+// The colours the terminal is able to display.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    // `NO_COLOR` is set or the terminal is dumb: text attributes are still
+    // used, but colours are not.
+    None,
+}
+
+impl ColorSupport {
+    pub fn detect() -> Self {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let term = env::var("TERM").unwrap_or_default();
+        if no_color || term == "dumb" {
+            return Self::None;
+        }
+        let color_term = env::var("COLORTERM").unwrap_or_default();
+        if matches!(color_term.as_str(), "truecolor" | "24bit") || term.ends_with("-direct") {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+
+    // Returns the closest colour the terminal can display, or None if colours
+    // should not be used at all.
+    pub fn convert(self, color: Color) -> Option<Color> {
+        match (self, color) {
+            (Self::None, _) => None,
+            (Self::TrueColor, _) | (_, Color::Reset) => Some(color),
+            (Self::Ansi256, Color::Rgb { r, g, b }) => {
+                Some(Color::AnsiValue(nearest_256((r, g, b))))
+            }
+            (Self::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_16((r, g, b))),
+            (Self::Ansi16, Color::AnsiValue(value)) => Some(nearest_16(ansi_256_to_rgb(value))),
+            (Self::Ansi256 | Self::Ansi16, _) => Some(color),
+        }
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+// Picks the closer of the nearest colour cube entry and the nearest gray ramp
+// entry. The 16 standard colours are skipped, as terminals often redefine them.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = rgb;
+    let cube_value = CUBE_START
+        .saturating_add(nearest_cube_level(r).saturating_mul(36))
+        .saturating_add(nearest_cube_level(g).saturating_mul(6))
+        .saturating_add(nearest_cube_level(b));
+    let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+    // The gray ramp runs from 8 to 238 in steps of 10
+    let gray_step = u8::try_from(average.saturating_sub(3) / 10)
+        .unwrap_or(u8::MAX)
+        .min(23);
+    let gray_value = GRAY_START.saturating_add(gray_step);
+    if distance(rgb, ansi_256_to_rgb(gray_value)) < distance(rgb, ansi_256_to_rgb(cube_value)) {
+        gray_value
+    } else {
+        cube_value
+    }
+}
+
+fn nearest_cube_level(channel: u8) -> u8 {
+    let nearest = CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| channel.abs_diff(**level))
+        .map_or(0, |(idx, _)| idx);
+    u8::try_from(nearest).unwrap_or(0)
+}
+
+fn ansi_256_to_rgb(value: u8) -> (u8, u8, u8) {
+    if value < CUBE_START {
+        return ANSI_16[usize::from(value)].1;
+    }
+    if value >= GRAY_START {
+        let gray = value
+            .saturating_sub(GRAY_START)
+            .saturating_mul(10)
+            .saturating_add(8);
+        return (gray, gray, gray);
+    }
+    let idx = value.saturating_sub(CUBE_START);
+    let level = |step: u8| CUBE_LEVELS[usize::from(step % 6)];
+    (level(idx / 36), level(idx / 6), level(idx))
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| u32::from(x.abs_diff(y)).pow(2);
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_corners_are_exact() {
+        assert_eq!(nearest_256((0, 0, 0)), 16);
+        assert_eq!(nearest_256((255, 0, 0)), 196);
+        assert_eq!(nearest_256((0, 255, 0)), 46);
+        assert_eq!(nearest_256((0, 0, 255)), 21);
+        assert_eq!(nearest_256((255, 255, 255)), 231);
+        assert_eq!(ansi_256_to_rgb(16), (0, 0, 0));
+        assert_eq!(ansi_256_to_rgb(196), (255, 0, 0));
+        assert_eq!(ansi_256_to_rgb(231), (255, 255, 255));
+    }
+
+    #[test]
+    fn grays_use_the_gray_ramp() {
+        assert_eq!(nearest_256((0x80, 0x80, 0x80)), 244);
+        assert_eq!(nearest_256((0x08, 0x08, 0x08)), 232);
+        assert_eq!(nearest_256((0xee, 0xee, 0xee)), 255);
+        assert_eq!(ansi_256_to_rgb(244), (128, 128, 128));
+        // Grays matching a cube level exactly stay in the cube
+        assert_eq!(nearest_256((95, 95, 95)), 59);
+    }
+
+    #[test]
+    fn palette_entries_map_to_themselves() {
+        for value in CUBE_START..=u8::MAX {
+            assert_eq!(nearest_256(ansi_256_to_rgb(value)), value);
+        }
+    }
+
+    #[test]
+    fn primaries_map_to_standard_colors() {
+        assert_eq!(nearest_16((0, 0, 0)), Color::Black);
+        assert_eq!(nearest_16((255, 0, 0)), Color::Red);
+        assert_eq!(nearest_16((0, 255, 0)), Color::Green);
+        // The bright blue of xterm is lighter than pure blue
+        assert_eq!(nearest_16((0, 0, 255)), Color::DarkBlue);
+        assert_eq!(nearest_16((255, 255, 255)), Color::White);
+    }
+
+    #[test]
+    fn palette_colors_are_downsampled_to_16() {
+        let convert = |value| ColorSupport::Ansi16.convert(Color::AnsiValue(value));
+        assert_eq!(convert(9), Some(Color::Red));
+        assert_eq!(convert(196), Some(Color::Red));
+        assert_eq!(convert(21), Some(Color::DarkBlue));
+        assert_eq!(convert(232), Some(Color::Black));
+        assert_eq!(convert(244), Some(Color::DarkGrey));
+        assert_eq!(convert(255), Some(Color::Grey));
+    }
+
+    #[test]
+    fn colors_are_converted_to_the_supported_ones() {
+        let orange = Color::Rgb {
+            r: 255,
+            g: 135,
+            b: 0,
+        };
+        assert_eq!(ColorSupport::TrueColor.convert(orange), Some(orange));
+        assert_eq!(
+            ColorSupport::Ansi256.convert(orange),
+            Some(Color::AnsiValue(208))
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.convert(orange),
+            Some(Color::DarkYellow)
+        );
+        assert_eq!(
+            ColorSupport::Ansi256.convert(Color::Cyan),
+            Some(Color::Cyan)
+        );
+        assert_eq!(
+            ColorSupport::Ansi16.convert(Color::Reset),
+            Some(Color::Reset)
+        );
+    }
+
+    #[test]
+    fn no_colors_are_used_without_support() {
+        for color in [
+            Color::Reset,
+            Color::Red,
+            Color::AnsiValue(208),
+            Color::Rgb { r: 1, g: 2, b: 3 },
+        ] {
+            assert_eq!(ColorSupport::None.convert(color), None);
+        }
+    }
+}
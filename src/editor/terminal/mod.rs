@@ -1,13 +1,18 @@
-use std::io::{Error, Write, stdout};
+use std::{
+    io::{Error, Write, stdout},
+    sync::OnceLock,
+};
 
-use attribute::Attribute;
+pub use caret_style::CaretStyle;
+use color_support::ColorSupport;
 use crossterm::{
     Command,
-    cursor::{Hide, MoveTo, Show},
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
     queue,
     style::{
-        Attribute::{Reset, Reverse},
-        Print, ResetColor, SetBackgroundColor, SetForegroundColor,
+        Attribute::{Bold, Italic, Reset, Reverse, Underlined},
+        Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
     },
     terminal::{
         Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen,
@@ -15,10 +20,18 @@ use crossterm::{
     },
 };
 
-use super::{Position, Size, annotated_string::AnnotatedString};
+use super::{
+    Position, Size,
+    annotated_string::AnnotatedString,
+    theme::{Style, Theme, ThemeElement},
+};
 use crate::prelude::*;
 
-mod attribute;
+mod caret_style;
+mod color_support;
+
+// Detected once, on first use
+static COLOR_SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
 
 pub struct Terminal;
 
@@ -27,6 +40,8 @@ impl Terminal {
         enable_raw_mode()?;
         Self::enter_alternate_screen()?;
         Self::disable_line_warp()?;
+        Self::enable_mouse_capture()?;
+        Self::queue_command(EnableBracketedPaste)?;
         Self::clean_screen()?;
         Self::execute()?;
         Ok(())
@@ -35,6 +50,9 @@ impl Terminal {
     pub fn terminate() -> Result<(), Error> {
         Self::leave_alternate_screen()?;
         Self::enable_line_warp()?;
+        Self::disable_mouse_capture()?;
+        Self::queue_command(DisableBracketedPaste)?;
+        Self::set_caret_style(CaretStyle::Default)?;
         Self::show_caret()?;
         Self::execute()?;
         disable_raw_mode()?;
@@ -58,12 +76,12 @@ impl Terminal {
     }
 
     pub fn enter_alternate_screen() -> Result<(), Error> {
-        Self::queue_command(LeaveAlternateScreen)?;
+        Self::queue_command(EnterAlternateScreen)?;
         Ok(())
     }
 
     pub fn leave_alternate_screen() -> Result<(), Error> {
-        Self::queue_command(EnterAlternateScreen)?;
+        Self::queue_command(LeaveAlternateScreen)?;
         Ok(())
     }
 
@@ -77,15 +95,13 @@ impl Terminal {
         Ok(())
     }
 
-    pub fn print(string: &str) -> Result<(), Error> {
-        Self::queue_command(Print(string))?;
+    pub fn set_caret_style(caret_style: CaretStyle) -> Result<(), Error> {
+        Self::queue_command(SetCursorStyle::from(caret_style))?;
         Ok(())
     }
 
-    pub fn print_row(row: RowIdx, line_text: &str) -> Result<(), Error> {
-        Self::move_caret_to(Position { row, col: 0 })?;
-        Self::clean_line()?;
-        Self::print(line_text)?;
+    pub fn print(string: &str) -> Result<(), Error> {
+        Self::queue_command(Print(string))?;
         Ok(())
     }
 
@@ -113,51 +129,95 @@ impl Terminal {
         Ok(())
     }
 
+    pub fn enable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(EnableMouseCapture)?;
+        Ok(())
+    }
+
+    pub fn disable_mouse_capture() -> Result<(), Error> {
+        Self::queue_command(DisableMouseCapture)?;
+        Ok(())
+    }
+
     pub fn set_title(title: &str) -> Result<(), Error> {
         Self::queue_command(SetTitle(title))?;
         Ok(())
     }
 
+    // Prints the row in the given style. The style is applied before the row is
+    // cleared, so that its background fills the whole row.
+    pub fn print_styled_row(row: RowIdx, line_text: &str, style: &Style) -> Result<(), Error> {
+        Self::move_caret_to(Position { row, col: 0 })?;
+        Self::set_style(style)?;
+        Self::clean_line()?;
+        Self::print(line_text)?;
+        Self::reset_style()?;
+        Ok(())
+    }
+
     pub fn print_annotated_row(
         row: RowIdx,
         annotated_string: &AnnotatedString,
+        theme: &Theme,
     ) -> Result<(), Error> {
+        let text_style = theme.style(ThemeElement::Text);
         Self::move_caret_to(Position { row, col: 0 })?;
+        Self::set_style(&text_style)?;
         Self::clean_line()?;
         annotated_string
             .into_iter()
             .try_for_each(|part| -> Result<(), Error> {
-                if let Some(annotation_type) = part.annotation_type {
-                    let attribute: Attribute = annotation_type.into();
-                    Self::set_attribute(&attribute)?;
+                if part.annotation_type.is_some() || part.background.is_some() {
+                    Self::set_style(
+                        &theme.annotation_style(part.background, part.annotation_type),
+                    )?;
+                    Self::print(part.string)?;
+                    Self::reset_style()?;
+                    Self::set_style(&text_style)?;
+                } else {
+                    Self::print(part.string)?;
                 }
-                Self::print(part.string)?;
-                Self::reset_color()?;
                 Ok(())
             })?;
+        Self::reset_style()?;
         Ok(())
     }
 
-    fn set_attribute(attribute: &Attribute) -> Result<(), Error> {
-        if let Some(foreground_color) = attribute.foreground {
+    // Sets the style, with its colours downsampled to what the terminal supports.
+    fn set_style(style: &Style) -> Result<(), Error> {
+        let color_support = *COLOR_SUPPORT.get_or_init(ColorSupport::detect);
+        if let Some(foreground_color) = style
+            .foreground
+            .and_then(|color| color_support.convert(color))
+        {
             Self::queue_command(SetForegroundColor(foreground_color))?;
         }
-        if let Some(background_color) = attribute.background {
+        if let Some(background_color) = style
+            .background
+            .and_then(|color| color_support.convert(color))
+        {
             Self::queue_command(SetBackgroundColor(background_color))?;
         }
+        if style.bold {
+            Self::queue_command(SetAttribute(Bold))?;
+        }
+        if style.italic {
+            Self::queue_command(SetAttribute(Italic))?;
+        }
+        if style.underline {
+            Self::queue_command(SetAttribute(Underlined))?;
+        }
+        if style.reverse {
+            Self::queue_command(SetAttribute(Reverse))?;
+        }
         Ok(())
     }
 
-    fn reset_color() -> Result<(), Error> {
-        Self::queue_command(ResetColor)?;
+    fn reset_style() -> Result<(), Error> {
+        Self::queue_command(SetAttribute(Reset))?;
         Ok(())
     }
 
-    pub fn print_inverted_row(row: RowIdx, line_text: &str) -> Result<(), Error> {
-        let width = Self::size()?.width;
-        Self::print_row(row, &format!("{Reverse}{line_text:width$.width$}{Reset}"))
-    }
-
     fn queue_command<T: Command>(command: T) -> Result<(), Error> {
         queue!(stdout(), command)?;
         Ok(())
@@ -0,0 +1,162 @@
+use std::{
+    collections::HashMap,
+    fs::{read_dir, read_to_string},
+    path::Path,
+};
+
+use super::{AnnotationType, config};
+
+mod style;
+mod theme_element;
+
+pub use style::Style;
+pub use theme_element::ThemeElement;
+
+pub const DEFAULT_THEME: &str = "dark";
+const FILE_EXTENSION: &str = "theme";
+const DIRECTORY_NAME: &str = "themes";
+
+const BUILT_IN_THEMES: [(&str, &str); 4] = [
+    ("dark", include_str!("themes/dark.theme")),
+    ("light", include_str!("themes/light.theme")),
+    (
+        "solarized-dark",
+        include_str!("themes/solarized-dark.theme"),
+    ),
+    (
+        "solarized-light",
+        include_str!("themes/solarized-light.theme"),
+    ),
+];
+
+// A named set of styles. Theme files contain one `element = style` line per
+// element, see `ThemeElement` and `Style` for the accepted values. Empty lines
+// and lines starting with `#` are ignored.
+#[derive(Clone, Default)]
+pub struct Theme {
+    name: String,
+    styles: HashMap<ThemeElement, Style>,
+}
+
+impl Theme {
+    pub fn parse(name: &str, source: &str) -> Result<Self, String> {
+        let mut styles = HashMap::new();
+        for (idx, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_number = idx.saturating_add(1);
+            let (element, style) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{name} line {line_number}: expected `element = style`"))?;
+            let element = ThemeElement::try_from(element.trim())
+                .map_err(|err| format!("{name} line {line_number}: {err}"))?;
+            let style = Style::try_from(style)
+                .map_err(|err| format!("{name} line {line_number}: {err}"))?;
+            styles.insert(element, style);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            styles,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Returns the style of the given element drawn on top of the text style.
+    pub fn style(&self, element: ThemeElement) -> Style {
+        let text = self.own_style(ThemeElement::Text);
+        if element == ThemeElement::Text {
+            text
+        } else {
+            text.patch(self.own_style(element))
+        }
+    }
+
+    // Returns the style of the annotation drawn on top of the text, or on top
+    // of a background annotation such as the current line.
+    pub fn annotation_style(
+        &self,
+        background: Option<AnnotationType>,
+        annotation_type: Option<AnnotationType>,
+    ) -> Style {
+        background.into_iter().chain(annotation_type).fold(
+            self.style(ThemeElement::Text),
+            |style, annotation_type| {
+                style.patch(self.own_style(ThemeElement::Annotation(annotation_type)))
+            },
+        )
+    }
+
+    fn own_style(&self, element: ThemeElement) -> Style {
+        self.styles.get(&element).copied().unwrap_or_default()
+    }
+}
+
+// All themes available for selection: the built-in ones, plus the `.theme`
+// files in the `themes` folder of the config directory. User themes replace
+// built-in themes of the same name.
+#[derive(Default)]
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Themes {
+    // Loads all themes. Themes which fail to load are skipped and reported in
+    // the returned list of errors.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut themes = Self::default();
+        let mut errors = Vec::new();
+        for (name, source) in BUILT_IN_THEMES {
+            match Theme::parse(name, source) {
+                Ok(theme) => themes.insert(theme),
+                Err(err) => errors.push(err),
+            }
+        }
+        if let Some(directory) = config::directory() {
+            themes.load_directory(&directory.join(DIRECTORY_NAME), &mut errors);
+        }
+        themes.themes.sort_by(|a, b| a.name.cmp(&b.name));
+        (themes, errors)
+    }
+
+    fn load_directory(&mut self, directory: &Path, errors: &mut Vec<String>) {
+        let Ok(entries) = read_dir(directory) else {
+            return;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path
+                .extension()
+                .is_none_or(|extension| extension != FILE_EXTENSION)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let theme = read_to_string(&path)
+                .map_err(|err| format!("Could not read {}: {err}", path.display()))
+                .and_then(|source| Theme::parse(name, &source));
+            match theme {
+                Ok(theme) => self.insert(theme),
+                Err(err) => errors.push(err),
+            }
+        }
+    }
+
+    fn insert(&mut self, theme: Theme) {
+        self.themes.retain(|existing| existing.name != theme.name);
+        self.themes.push(theme);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|theme| theme.name == name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.themes.iter().map(Theme::name).collect()
+    }
+}
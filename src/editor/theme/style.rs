@@ -0,0 +1,76 @@
+use crossterm::style::Color;
+
+// How a piece of text is drawn. Unset colours fall back to the style
+// underneath, or to the terminal default.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct Style {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+impl Style {
+    // Returns this style with the colours and attributes of `other` layered on
+    // top.
+    pub fn patch(self, other: Self) -> Self {
+        Self {
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+            bold: self.bold || other.bold,
+            italic: self.italic || other.italic,
+            underline: self.underline || other.underline,
+            reverse: self.reverse || other.reverse,
+        }
+    }
+}
+
+// Parses a whitespace separated list such as `fg=#ffb366 bg=black bold`.
+// Colours are given as `#rrggbb`, as an ANSI palette index or by name.
+impl TryFrom<&str> for Style {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut style = Self::default();
+        for token in value.split_whitespace() {
+            match token {
+                "bold" => style.bold = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "reverse" => style.reverse = true,
+                _ => {
+                    if let Some(color) = token.strip_prefix("fg=") {
+                        style.foreground = Some(parse_color(color)?);
+                    } else if let Some(color) = token.strip_prefix("bg=") {
+                        style.background = Some(parse_color(color)?);
+                    } else {
+                        return Err(format!("Unknown style attribute: {token}"));
+                    }
+                }
+            }
+        }
+        Ok(style)
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let component = |range| {
+            hex.get(range)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+        };
+        if hex.len() == 6 {
+            if let (Some(r), Some(g), Some(b)) = (component(0..2), component(2..4), component(4..6))
+            {
+                return Ok(Color::Rgb { r, g, b });
+            }
+        }
+        return Err(format!("Invalid colour: {value}"));
+    }
+    if let Ok(ansi_value) = value.parse::<u8>() {
+        return Ok(Color::AnsiValue(ansi_value));
+    }
+    Color::try_from(value).map_err(|()| format!("Unknown colour: {value}"))
+}
@@ -0,0 +1,52 @@
+use super::super::AnnotationType;
+
+// Everything a theme can assign a style to.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ThemeElement {
+    // The base style of the text area, which all other elements are drawn on.
+    Text,
+    StatusBar,
+    MessageBar,
+    // The `~` markers below the end of the buffer.
+    Gutter,
+    Annotation(AnnotationType),
+}
+
+impl TryFrom<&str> for ThemeElement {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let annotation_type = match value {
+            "text" => return Ok(Self::Text),
+            "status_bar" => return Ok(Self::StatusBar),
+            "message_bar" => return Ok(Self::MessageBar),
+            "gutter" => return Ok(Self::Gutter),
+            "match" => AnnotationType::Match,
+            "selected_match" => AnnotationType::SelectedMatch,
+            "number" => AnnotationType::Number,
+            "keyword" => AnnotationType::KeyWord,
+            "type" => AnnotationType::Type,
+            "known_value" => AnnotationType::KnownValue,
+            "char" => AnnotationType::Char,
+            "lifetime_specifier" => AnnotationType::LifetimeSpecifier,
+            "comment" => AnnotationType::Comment,
+            "doc_comment" => AnnotationType::DocComment,
+            "string" => AnnotationType::String,
+            "escape" => AnnotationType::Escape,
+            "attribute" => AnnotationType::Attribute,
+            "macro" => AnnotationType::Macro,
+            "selection" => AnnotationType::Selection,
+            "matching_bracket" => AnnotationType::MatchingBracket,
+            "secondary_caret" => AnnotationType::SecondaryCaret,
+            "fold_marker" => AnnotationType::FoldMarker,
+            "occurrence" => AnnotationType::Occurrence,
+            "whitespace" => AnnotationType::Whitespace,
+            "trailing_whitespace" => AnnotationType::TrailingWhitespace,
+            "indent_guide" => AnnotationType::IndentGuide,
+            "ruler" => AnnotationType::Ruler,
+            "current_line" => AnnotationType::CurrentLine,
+            _ => return Err(format!("Unknown theme element: {value}")),
+        };
+        Ok(Self::Annotation(annotation_type))
+    }
+}
@@ -1,7 +1,7 @@
 use std::{cmp::min, io::Error};
 
 use super::{
-    super::{Line, Terminal, command::Edit},
+    super::{Line, Terminal, Theme, ThemeElement, command::Edit},
     UIComponent,
 };
 use crate::prelude::*;
@@ -18,7 +18,33 @@ impl CommandBar {
     pub fn handle_edit_command(&mut self, command: Edit) {
         match command {
             Edit::Insert(character) => self.value.append_char(character),
-            Edit::Delete | Edit::InsertNewLine => {}
+            Edit::Indent => self.value.append_char('\t'),
+            // The command bar holds a single line, so line breaks are dropped
+            Edit::InsertText(text) => {
+                let text: String = text
+                    .chars()
+                    .filter(|&character| character != '\n')
+                    .collect();
+                self.value.append(&Line::from(&text));
+            }
+            Edit::Delete
+            | Edit::DeleteWordForward
+            | Edit::DeleteWordBackward
+            | Edit::DeleteLines
+            | Edit::DuplicateLines
+            | Edit::MoveLinesUp
+            | Edit::MoveLinesDown
+            | Edit::JoinLines
+            | Edit::SortLines
+            | Edit::ToggleComment
+            | Edit::Dedent
+            | Edit::InsertNewLine
+            | Edit::Cut
+            | Edit::CutAppend
+            | Edit::Copy
+            | Edit::Paste
+            | Edit::PasteAfter
+            | Edit::CyclePaste => {}
             Edit::DeleteBackward => self.value.delete_last(),
         }
         self.set_needs_redraw(true);
@@ -60,26 +86,27 @@ impl UIComponent for CommandBar {
         self.size = size;
     }
 
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin: RowIdx, theme: &Theme) -> Result<(), Error> {
         // this is how much space there is between the right side of the prompt and the
         // edge of the bar
         let area_for_value = self.size.width.saturating_sub(self.prompt.len());
         // we always want to show the left part of the value, therefore the end of the
         // visible range we try to access will be equal to the full width
-        let value_end = self.value.width();
+        let value_end = self.value.width(DEFAULT_TAB_WIDTH);
         // This should give us the start for the grapheme sub range we want to print
         // out.
         let value_start = value_end.saturating_sub(area_for_value);
         let message = format!(
             "{}{}",
             self.prompt,
-            self.value.get_visible_graphemes(value_start..value_end)
+            self.value
+                .get_visible_graphemes(value_start..value_end, DEFAULT_TAB_WIDTH)
         );
         let to_print = if message.len() <= self.size.width {
             message
         } else {
             String::new()
         };
-        Terminal::print_row(origin, &to_print)
+        Terminal::print_styled_row(origin, &to_print, &theme.style(ThemeElement::MessageBar))
     }
 }
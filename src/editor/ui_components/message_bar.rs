@@ -3,7 +3,10 @@ use std::{
     time::{Duration, Instant},
 };
 
-use super::{super::Terminal, UIComponent};
+use super::{
+    super::{Terminal, Theme, ThemeElement},
+    UIComponent,
+};
 use crate::prelude::*;
 
 const DEFAULT_DURATION: Duration = Duration::new(5, 0);
@@ -56,7 +59,7 @@ impl UIComponent for MessageBar {
 
     fn set_size(&mut self, _: Size) {}
 
-    fn draw(&mut self, origin: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin: RowIdx, theme: &Theme) -> Result<(), Error> {
         if self.current_message.is_expired() {
             self.cleared_after_expiry = true;
         }
@@ -66,6 +69,6 @@ impl UIComponent for MessageBar {
             &self.current_message.text
         };
 
-        Terminal::print_row(origin, message)
+        Terminal::print_styled_row(origin, message, &theme.style(ThemeElement::MessageBar))
     }
 }
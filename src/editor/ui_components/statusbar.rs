@@ -1,7 +1,7 @@
 use std::io::Error;
 
 use super::{
-    super::{DocumentStatus, Terminal},
+    super::{DocumentStatus, Terminal, Theme, ThemeElement},
     UIComponent,
 };
 use crate::prelude::*;
@@ -35,12 +35,14 @@ impl UIComponent for StatusBar {
         self.size = size;
     }
 
-    fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error> {
+    fn draw(&mut self, origin_row: RowIdx, theme: &Theme) -> Result<(), Error> {
         let line_count = self.current_status.line_count_to_string();
         let modified_indicator = self.current_status.modified_indicator_to_string();
+        let mode_indicator = self.current_status.mode_indicator_to_string();
+        let recording_indicator = self.current_status.recording_indicator_to_string();
 
         let beginning = format!(
-            "{} - {line_count} {modified_indicator}",
+            "{mode_indicator}{recording_indicator}{} - {line_count} {modified_indicator}",
             self.current_status.file_name
         );
 
@@ -52,12 +54,18 @@ impl UIComponent for StatusBar {
         let remainder_len = self.size.width.saturating_sub(beginning.len());
         let status = format!("{beginning}{back_part:>remainder_len$}");
 
-        let to_print = if status.len() <= self.size.width {
+        let width = self.size.width;
+        let to_print = if status.len() <= width {
             status
         } else {
             String::new()
         };
-        let _ = Terminal::print_inverted_row(origin_row, &to_print);
+        // Pad to the full width, so that reversed styles cover the whole bar
+        let _ = Terminal::print_styled_row(
+            origin_row,
+            &format!("{to_print:width$}"),
+            &theme.style(ThemeElement::StatusBar),
+        );
 
         Ok(())
     }
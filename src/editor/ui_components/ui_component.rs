@@ -1,5 +1,6 @@
 use std::io::Error;
 
+use super::super::Theme;
 use crate::prelude::*;
 
 pub trait UIComponent {
@@ -11,9 +12,9 @@ pub trait UIComponent {
     }
     fn set_size(&mut self, size: Size);
 
-    fn render(&mut self, origin_row: RowIdx) {
+    fn render(&mut self, origin_row: RowIdx, theme: &Theme) {
         if self.needs_redraw() {
-            if let Err(err) = self.draw(origin_row) {
+            if let Err(err) = self.draw(origin_row, theme) {
                 #[cfg(debug_assertions)]
                 {
                     panic!("Could not render component! {err:?}");
@@ -27,5 +28,5 @@ pub trait UIComponent {
             }
         }
     }
-    fn draw(&mut self, origin_row: RowIdx) -> Result<(), Error>;
+    fn draw(&mut self, origin_row: RowIdx, theme: &Theme) -> Result<(), Error>;
 }
@@ -0,0 +1,129 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::prelude::*;
+
+// The number of locations kept in the jump list.
+const JUMP_LIST_CAPACITY: usize = 100;
+
+// Named marks and the jump list of a buffer. The buffer moves the locations
+// along with its edits, so that they keep pointing at the same text.
+#[derive(Default)]
+pub struct Bookmarks {
+    marks: HashMap<char, Location>,
+    // Locations left by jumps, oldest first.
+    jumps: Vec<Location>,
+    // The position within the jump list. It equals the length of the list
+    // unless jumping back.
+    jump_idx: usize,
+}
+
+impl Bookmarks {
+    pub fn set_mark(&mut self, name: char, location: Location) {
+        self.marks.insert(name, location);
+    }
+
+    pub fn mark(&self, name: char) -> Option<Location> {
+        self.marks.get(&name).copied()
+    }
+
+    // Records the location left by a jump. Locations further ahead in the list
+    // are dropped, as are older entries on the same line.
+    pub fn push_jump(&mut self, location: Location) {
+        self.jumps.truncate(self.jump_idx);
+        self.jumps.retain(|jump| jump.line_idx != location.line_idx);
+        self.jumps.push(location);
+        if self.jumps.len() > JUMP_LIST_CAPACITY {
+            self.jumps.remove(0);
+        }
+        self.jump_idx = self.jumps.len();
+    }
+
+    // Returns the previous location in the jump list. When leaving the end of
+    // the list, the current location is recorded, so that jumping forward
+    // returns to it.
+    pub fn jump_back(&mut self, current: Location) -> Option<Location> {
+        let idx = self.jump_idx.checked_sub(1)?;
+        if self.jump_idx == self.jumps.len() {
+            self.jumps.push(current);
+        }
+        self.jump_idx = idx;
+        self.jumps.get(idx).copied()
+    }
+
+    pub fn jump_forward(&mut self) -> Option<Location> {
+        let idx = self.jump_idx.saturating_add(1);
+        let location = self.jumps.get(idx).copied()?;
+        self.jump_idx = idx;
+        Some(location)
+    }
+
+    // region: following edits
+
+    // Follows the replacement of the text from `start` to `old_end` by text
+    // ending at `new_end`. Locations within the replaced text move to its start,
+    // those behind it move along with the text following it.
+    pub fn replace(&mut self, start: Location, old_end: Location, new_end: Location) {
+        for location in self.locations_mut() {
+            if *location <= start {
+                continue;
+            }
+            *location = if *location < old_end {
+                start
+            } else if location.line_idx == old_end.line_idx {
+                Location {
+                    line_idx: new_end.line_idx,
+                    grapheme_idx: location
+                        .grapheme_idx
+                        .saturating_sub(old_end.grapheme_idx)
+                        .saturating_add(new_end.grapheme_idx),
+                }
+            } else {
+                Location {
+                    line_idx: location
+                        .line_idx
+                        .saturating_sub(old_end.line_idx)
+                        .saturating_add(new_end.line_idx),
+                    grapheme_idx: location.grapheme_idx,
+                }
+            };
+        }
+    }
+
+    pub fn insert_lines(&mut self, at: LineIdx, count: usize) {
+        self.map_lines(|line_idx| {
+            if line_idx >= at {
+                line_idx.saturating_add(count)
+            } else {
+                line_idx
+            }
+        });
+    }
+
+    // Follows the removal of whole lines. Locations on them move to the start of
+    // the line taking their place.
+    pub fn remove_lines(&mut self, lines: Range<LineIdx>) {
+        for location in self.locations_mut() {
+            if lines.contains(&location.line_idx) {
+                *location = Location {
+                    line_idx: lines.start,
+                    grapheme_idx: 0,
+                };
+            } else if location.line_idx >= lines.end {
+                location.line_idx = location.line_idx.saturating_sub(lines.len());
+            }
+        }
+    }
+
+    // Follows lines being reordered, given the new index of each line.
+    pub fn map_lines(&mut self, new_idx: impl Fn(LineIdx) -> LineIdx) {
+        for location in self.locations_mut() {
+            location.line_idx = new_idx(location.line_idx);
+        }
+    }
+
+    fn locations_mut(&mut self) -> impl Iterator<Item = &mut Location> {
+        self.marks.values_mut().chain(self.jumps.iter_mut())
+    }
+
+    // region end
+}
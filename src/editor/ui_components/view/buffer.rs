@@ -1,17 +1,41 @@
 use std::{
-    fs::{File, read_to_string},
+    cmp::{max, min},
+    collections::BTreeMap,
+    fs::{File, read},
     io::{Error, Write},
     ops::Range,
+    path::Path,
 };
 
-use super::{FileInfo, Line, highlighter::Highlighter};
-use crate::{editor::annotated_string::AnnotatedString, prelude::*};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+    Bookmarks, FileInfo, Folds, Line,
+    editor_config::{EditorConfig, LineEnding},
+    highlighter::{Highlighter, Syntax, TextEdit, TextPosition},
+};
+use crate::{
+    editor::{AnnotationType, annotated_string::AnnotatedString},
+    prelude::*,
+};
+
+const BRACKETS: [(&str, &str); 3] = [("(", ")"), ("[", "]"), ("{", "}")];
 
 #[derive(Default)]
 pub struct Buffer {
     lines: Vec<Line>,
     file_info: FileInfo,
+    editor_config: EditorConfig,
     dirty: bool,
+    // Whether the file ended in a line break when it was last loaded or saved.
+    ends_with_newline: bool,
+    bookmarks: Bookmarks,
+    folds: Folds,
+    syntax: Syntax,
+    // The offset of the start of each line, as far as it is known. Edits drop
+    // the offsets behind their first line.
+    #[cfg(feature = "tree-sitter")]
+    line_offsets: Vec<ByteIdx>,
 }
 
 impl Buffer {
@@ -23,24 +47,85 @@ impl Buffer {
         &self.file_info
     }
 
+    pub const fn editor_config(&self) -> &EditorConfig {
+        &self.editor_config
+    }
+
+    pub const fn bookmarks(&self) -> &Bookmarks {
+        &self.bookmarks
+    }
+
+    pub const fn bookmarks_mut(&mut self) -> &mut Bookmarks {
+        &mut self.bookmarks
+    }
+
+    pub const fn folds(&self) -> &Folds {
+        &self.folds
+    }
+
+    pub const fn folds_mut(&mut self) -> &mut Folds {
+        &mut self.folds
+    }
+
     pub fn grapheme_count(&self, idx: LineIdx) -> GraphemeIdx {
         self.lines.get(idx).map_or(0, Line::grapheme_count)
     }
 
-    pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx) -> GraphemeIdx {
+    pub fn first_non_blank(&self, idx: LineIdx) -> GraphemeIdx {
+        self.lines.get(idx).map_or(0, Line::first_non_blank)
+    }
+
+    pub fn leading_whitespace(&self, idx: LineIdx) -> &str {
         self.lines
             .get(idx)
-            .map_or(0, |line| line.width_until(until))
+            .map_or("", |line| line.sub_str(0..line.first_non_blank()))
+    }
+
+    pub fn width_until(&self, idx: LineIdx, until: GraphemeIdx, tab_width: ColIdx) -> ColIdx {
+        self.lines
+            .get(idx)
+            .map_or(0, |line| line.width_until(until, tab_width))
+    }
+
+    pub fn grapheme_idx_at(&self, idx: LineIdx, col: ColIdx, tab_width: ColIdx) -> GraphemeIdx {
+        self.lines
+            .get(idx)
+            .map_or(0, |line| line.grapheme_idx_at(col, tab_width))
+    }
+
+    // Returns the grapheme at the given location, if any.
+    pub fn grapheme_at(&self, at: Location) -> Option<&str> {
+        let line = self.lines.get(at.line_idx)?;
+        (at.grapheme_idx < line.grapheme_count())
+            .then(|| line.sub_str(at.grapheme_idx..at.grapheme_idx.saturating_add(1)))
+    }
+
+    // Returns the range of the word containing the given location, if any.
+    pub fn word_range_at(&self, at: Location) -> Option<Range<GraphemeIdx>> {
+        self.lines
+            .get(at.line_idx)?
+            .word_ranges()
+            .into_iter()
+            .find(|range| range.contains(&at.grapheme_idx))
     }
 
     pub fn get_highlighted_substring(
         &self,
         line_idx: LineIdx,
-        range: Range<GraphemeIdx>,
+        range: Range<ColIdx>,
         highlighter: &Highlighter,
+        tab_width: ColIdx,
+        show_whitespace: bool,
+        indent_guides: Option<ColIdx>,
     ) -> Option<AnnotatedString> {
         self.lines.get(line_idx).map(|line| {
-            line.get_annotated_visible_sub_str(range, Some(&highlighter.get_annotations(line_idx)))
+            line.get_annotated_visible_sub_str(
+                range,
+                Some(&highlighter.get_annotations(line_idx)),
+                tab_width,
+                show_whitespace,
+                indent_guides,
+            )
         })
     }
 
@@ -51,15 +136,37 @@ impl Buffer {
     }
 
     pub fn load(file_name: &str) -> Result<Self, Error> {
-        let contents = read_to_string(file_name)?;
+        let editor_config = EditorConfig::for_file(Path::new(file_name));
+        let contents = editor_config
+            .charset
+            .unwrap_or_default()
+            .decode(read(file_name)?)?;
         let mut lines = Vec::new();
-        for value in contents.lines() {
-            lines.push(Line::from(value));
+        let ends_with_newline;
+        if editor_config.end_of_line == Some(LineEnding::Cr) {
+            ends_with_newline = contents.ends_with('\r');
+            let contents = contents.strip_suffix('\r').unwrap_or(&contents);
+            if !contents.is_empty() {
+                lines.extend(contents.split('\r').map(Line::from));
+            }
+        } else {
+            ends_with_newline = contents.ends_with('\n');
+            for value in contents.lines() {
+                lines.push(Line::from(value));
+            }
         }
+        let file_info = FileInfo::from(file_name);
         Ok(Self {
             lines,
-            file_info: FileInfo::from(file_name),
+            syntax: Syntax::new(file_info.get_file_type()),
+            file_info,
+            editor_config,
             dirty: false,
+            ends_with_newline,
+            bookmarks: Bookmarks::default(),
+            folds: Folds::default(),
+            #[cfg(feature = "tree-sitter")]
+            line_offsets: Vec::new(),
         })
     }
 
@@ -127,35 +234,195 @@ impl Buffer {
         None
     }
 
-    fn save_to_file(&self, file_info: &FileInfo) -> Result<(), Error> {
+    // Writes the lines to the file, in the format given by the EditorConfig
+    // properties. Without them, lines end in `\n`, including the last one.
+    // With `insert_final_newline = false`, the last line keeps the line break
+    // it had before. Returns whether the file now ends in a line break.
+    fn save_to_file(
+        &self,
+        file_info: &FileInfo,
+        editor_config: &EditorConfig,
+    ) -> Result<bool, Error> {
+        let mut ends_with_newline = false;
         if let Some(file_path) = &file_info.get_path() {
-            let mut file = File::create(file_path)?;
-            for line in &self.lines {
-                writeln!(file, "{line}")?;
+            let line_ending = editor_config.end_of_line.unwrap_or_default().as_str();
+            let mut contents = self
+                .lines
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(line_ending);
+            let final_newline = match editor_config.insert_final_newline {
+                Some(false) => self.ends_with_newline,
+                Some(true) | None => true,
+            };
+            if !self.lines.is_empty() && final_newline {
+                contents.push_str(line_ending);
+                ends_with_newline = true;
             }
+            let mut file = File::create(file_path)?;
+            file.write_all(&editor_config.charset.unwrap_or_default().encode(&contents))?;
         } else {
             #[cfg(debug_assertions)]
             {
                 panic!("Attempting to save with no file path present");
             }
         }
-        Ok(())
+        Ok(ends_with_newline)
     }
 
-    pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
+    pub fn save_as(
+        &mut self,
+        file_name: &str,
+        strip_trailing_whitespace: bool,
+    ) -> Result<(), Error> {
         let file_info = FileInfo::from(file_name);
-        self.save_to_file(&file_info)?;
+        let editor_config = EditorConfig::for_file(Path::new(file_name));
+        if editor_config
+            .trim_trailing_whitespace
+            .unwrap_or(strip_trailing_whitespace)
+        {
+            self.trim_trailing_whitespace();
+        }
+        self.ends_with_newline = self.save_to_file(&file_info, &editor_config)?;
+        if file_info.get_file_type() != self.file_info.get_file_type() {
+            self.syntax = Syntax::new(file_info.get_file_type());
+        }
         self.file_info = file_info;
+        self.editor_config = editor_config;
         self.dirty = false;
         Ok(())
     }
 
-    pub fn save(&mut self) -> Result<(), Error> {
-        self.save_to_file(&self.file_info)?;
+    // Saves the buffer, stripping trailing whitespace if the editorconfig
+    // settings ask for it, or else if `strip_trailing_whitespace` is set.
+    pub fn save(&mut self, strip_trailing_whitespace: bool) -> Result<(), Error> {
+        if self
+            .editor_config
+            .trim_trailing_whitespace
+            .unwrap_or(strip_trailing_whitespace)
+        {
+            self.trim_trailing_whitespace();
+        }
+        self.ends_with_newline = self.save_to_file(&self.file_info, &self.editor_config)?;
         self.dirty = false;
         Ok(())
     }
 
+    fn trim_trailing_whitespace(&mut self) {
+        let mut trimmed = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.ends_with(char::is_whitespace))
+            .map(|(line_idx, _)| line_idx);
+        let Some(first) = trimmed.next() else {
+            return;
+        };
+        let last = trimmed.next_back().unwrap_or(first);
+        let start = self.text_position(Location {
+            line_idx: first,
+            grapheme_idx: 0,
+        });
+        let old_end = self.text_position(self.line_end(last));
+        for (line_idx, line) in self.lines.iter_mut().enumerate() {
+            let old_end = Location {
+                line_idx,
+                grapheme_idx: line.grapheme_count(),
+            };
+            line.trim_end();
+            let new_end = Location {
+                line_idx,
+                grapheme_idx: line.grapheme_count(),
+            };
+            if new_end != old_end {
+                self.bookmarks.replace(new_end, old_end, new_end);
+            }
+        }
+        self.syntax_edited(start, old_end, self.line_end(last));
+    }
+
+    // Returns the position of the location in the text. Locations behind the
+    // last line lie at its end.
+    fn text_position(&mut self, at: Location) -> TextPosition {
+        let at = if at.line_idx < self.height() {
+            at
+        } else if let Some(last) = self.height().checked_sub(1) {
+            self.line_end(last)
+        } else {
+            return TextPosition::default();
+        };
+        let byte_idx = self.lines.get(at.line_idx).map_or(0, |line| {
+            line.grapheme_idx_to_byte_idx(min(at.grapheme_idx, line.grapheme_count()))
+        });
+        TextPosition {
+            line_idx: at.line_idx,
+            byte_idx,
+            #[cfg(feature = "tree-sitter")]
+            offset: self.line_offset(at.line_idx).saturating_add(byte_idx),
+        }
+    }
+
+    // Returns the offset of the start of the line, adding up the lengths of the
+    // lines above it whose offsets aren't known yet.
+    #[cfg(feature = "tree-sitter")]
+    fn line_offset(&mut self, idx: LineIdx) -> ByteIdx {
+        while self.line_offsets.len() <= idx {
+            let offset = self.line_offsets.len().checked_sub(1).map_or(0, |above| {
+                let above_offset = self.line_offsets.get(above).copied().unwrap_or_default();
+                let above_len = self.lines.get(above).map_or(0, |line| line.len());
+                above_offset.saturating_add(above_len).saturating_add(1)
+            });
+            self.line_offsets.push(offset);
+        }
+        self.line_offsets.get(idx).copied().unwrap_or_default()
+    }
+
+    fn line_end(&self, idx: LineIdx) -> Location {
+        Location {
+            line_idx: idx,
+            grapheme_idx: self.grapheme_count(idx),
+        }
+    }
+
+    // Returns the position of the start of the line, or the end of the text if
+    // there is no such line.
+    fn line_start_position(&mut self, idx: LineIdx) -> TextPosition {
+        self.text_position(Location {
+            line_idx: idx,
+            grapheme_idx: 0,
+        })
+    }
+
+    // Returns the positions of the start of the first and the end of the last
+    // of the lines.
+    fn lines_position(&mut self, lines: Range<LineIdx>) -> (TextPosition, TextPosition) {
+        (
+            self.line_start_position(lines.start),
+            self.text_position(self.line_end(lines.end.saturating_sub(1))),
+        )
+    }
+
+    // Invalidates the syntax between the positions, which are taken before the
+    // edit, and the new end, which is taken after it.
+    fn syntax_edited(&mut self, start: TextPosition, old_end: TextPosition, new_end: Location) {
+        #[cfg(feature = "tree-sitter")]
+        self.line_offsets.truncate(start.line_idx.saturating_add(1));
+        let new_end = self.text_position(new_end);
+        self.edit_syntax(&TextEdit {
+            start,
+            old_end,
+            new_end,
+        });
+    }
+
+    fn edit_syntax(&mut self, edit: &TextEdit) {
+        #[cfg(feature = "tree-sitter")]
+        self.line_offsets
+            .truncate(edit.start.line_idx.saturating_add(1));
+        self.syntax.edit(edit);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.lines.is_empty()
     }
@@ -170,44 +437,896 @@ impl Buffer {
 
     pub fn insert_char(&mut self, character: char, at: Location) {
         debug_assert!(at.line_idx <= self.height());
+        let start = self.text_position(at);
         if at.line_idx == self.height() {
+            let inserted = if self.is_empty() {
+                character.to_string()
+            } else {
+                format!("\n{character}")
+            };
             self.lines.push(Line::from(&character.to_string()));
             self.dirty = true;
+            self.edit_syntax(&TextEdit {
+                start,
+                old_end: start,
+                new_end: start.after(&inserted),
+            });
         } else if let Some(line) = self.lines.get_mut(at.line_idx) {
             line.insert_char(character, at.grapheme_idx);
             self.dirty = true;
+            self.edit_syntax(&TextEdit {
+                start,
+                old_end: start,
+                new_end: start.after(character.encode_utf8(&mut [0; 4])),
+            });
+            let end = Location {
+                line_idx: at.line_idx,
+                grapheme_idx: at.grapheme_idx.saturating_add(1),
+            };
+            self.bookmarks.replace(at, at, end);
         }
     }
 
     pub fn delete(&mut self, at: Location) {
+        let start = self.text_position(at);
         if let Some(line) = self.lines.get(at.line_idx) {
             if at.grapheme_idx >= line.grapheme_count()
                 && self.height() > at.line_idx.saturating_add(1)
             {
+                self.edit_syntax(&TextEdit {
+                    start,
+                    old_end: start.after("\n"),
+                    new_end: start,
+                });
                 let next_line = self.lines.remove(at.line_idx.saturating_add(1));
                 // clippy::indexing_slicing: We checked for existence of this line in the
                 // surrounding if statement
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_idx].append(&next_line);
                 self.dirty = true;
+                let next_line_start = Location {
+                    line_idx: at.line_idx.saturating_add(1),
+                    grapheme_idx: 0,
+                };
+                self.bookmarks.replace(at, next_line_start, at);
+                self.folds.lines_replaced(at.line_idx, 2, 1);
             } else if at.grapheme_idx < line.grapheme_count() {
+                let next_grapheme = Location {
+                    line_idx: at.line_idx,
+                    grapheme_idx: at.grapheme_idx.saturating_add(1),
+                };
+                let old_end = self.text_position(next_grapheme);
+                self.edit_syntax(&TextEdit {
+                    start,
+                    old_end,
+                    new_end: start,
+                });
                 // clippy::indexing_slicing: We checked for existence of this line in the
                 // surrounding if statement
                 #[allow(clippy::indexing_slicing)]
                 self.lines[at.line_idx].delete(at.grapheme_idx);
                 self.dirty = true;
+                self.bookmarks.replace(at, next_grapheme, at);
             }
         }
     }
 
     pub fn insert_newline(&mut self, at: Location) {
+        let start = self.text_position(at);
+        // An empty first line adds no text
+        let inserted = if self.is_empty() { "" } else { "\n" };
         if at.line_idx == self.height() {
             self.lines.push(Line::default());
             self.dirty = true;
+            self.edit_syntax(&TextEdit {
+                start,
+                old_end: start,
+                new_end: start.after(inserted),
+            });
         } else if let Some(line) = self.lines.get_mut(at.line_idx) {
             let new = line.split(at.grapheme_idx);
             self.lines.insert(at.line_idx.saturating_add(1), new);
             self.dirty = true;
+            self.edit_syntax(&TextEdit {
+                start,
+                old_end: start,
+                new_end: start.after(inserted),
+            });
+            let next_line_start = Location {
+                line_idx: at.line_idx.saturating_add(1),
+                grapheme_idx: 0,
+            };
+            self.bookmarks.replace(at, at, next_line_start);
+            self.folds.lines_replaced(at.line_idx, 1, 2);
+        }
+    }
+
+    // Inserts the given text, which may span multiple lines, and returns the
+    // location right behind the inserted text.
+    pub fn insert_text(&mut self, text: &str, at: Location) -> Location {
+        debug_assert!(at.line_idx <= self.height());
+        if text.is_empty() {
+            return at;
+        }
+        let start = self.text_position(at);
+        let mut inserted = text
+            .split('\n')
+            .map(|segment| segment.strip_suffix('\r').unwrap_or(segment))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if at.line_idx == self.height() {
+            if !self.is_empty() {
+                inserted.insert(0, '\n');
+            }
+            self.lines.push(Line::default());
+        }
+        let Some(line) = self.lines.get_mut(at.line_idx) else {
+            return at;
+        };
+        let remainder = line.split(at.grapheme_idx);
+        let mut segments = text
+            .split('\n')
+            .map(|segment| Line::from(segment.strip_suffix('\r').unwrap_or(segment)));
+        if let Some(first) = segments.next() {
+            line.append(&first);
+        }
+        let mut location = Location {
+            line_idx: at.line_idx,
+            grapheme_idx: line.grapheme_count(),
+        };
+        for segment in segments {
+            location.line_idx = location.line_idx.saturating_add(1);
+            location.grapheme_idx = segment.grapheme_count();
+            self.lines.insert(location.line_idx, segment);
+        }
+        if let Some(line) = self.lines.get_mut(location.line_idx) {
+            line.append(&remainder);
+        }
+        self.dirty = true;
+        self.edit_syntax(&TextEdit {
+            start,
+            old_end: start,
+            new_end: start.after(&inserted),
+        });
+        self.bookmarks.replace(at, at, location);
+        self.folds.lines_replaced(
+            at.line_idx,
+            1,
+            location
+                .line_idx
+                .saturating_sub(at.line_idx)
+                .saturating_add(1),
+        );
+        location
+    }
+
+    // Returns the text between the two locations, with lines separated by `\n`.
+    pub fn text_in_range(&self, range: Range<Location>) -> String {
+        let Range { start, end } = range;
+        let mut result = String::new();
+        for line_idx in start.line_idx..=end.line_idx {
+            let Some(line) = self.lines.get(line_idx) else {
+                break;
+            };
+            let from = if line_idx == start.line_idx {
+                start.grapheme_idx
+            } else {
+                0
+            };
+            let to = if line_idx == end.line_idx {
+                end.grapheme_idx
+            } else {
+                line.grapheme_count()
+            };
+            if line_idx != start.line_idx {
+                result.push('\n');
+            }
+            result.push_str(line.sub_str(from..to));
+        }
+        result
+    }
+
+    // Removes the text between the two locations, joining the first and last
+    // line of the range.
+    pub fn delete_range(&mut self, range: Range<Location>) {
+        let Range { start, end } = range;
+        debug_assert!(start <= end);
+        if start >= end || start.line_idx >= self.height() {
+            return;
+        }
+        let end_line_idx = min(end.line_idx, self.height().saturating_sub(1));
+        let start_position = self.text_position(start);
+        let old_end = self.text_position(end);
+        let remainder = if end.line_idx == end_line_idx {
+            self.lines
+                .get_mut(end_line_idx)
+                .map(|line| line.split(end.grapheme_idx))
+                .unwrap_or_default()
+        } else {
+            Line::default()
+        };
+        if let Some(line) = self.lines.get_mut(start.line_idx) {
+            line.split(start.grapheme_idx);
+            line.append(&remainder);
+        }
+        if end_line_idx > start.line_idx {
+            self.lines
+                .drain(start.line_idx.saturating_add(1)..=end_line_idx);
+        }
+        self.dirty = true;
+        self.edit_syntax(&TextEdit {
+            start: start_position,
+            old_end,
+            new_end: start_position,
+        });
+        self.bookmarks.replace(start, end, start);
+        self.folds.lines_replaced(
+            start.line_idx,
+            end_line_idx
+                .saturating_sub(start.line_idx)
+                .saturating_add(1),
+            1,
+        );
+    }
+
+    // region: line operations
+
+    pub fn delete_lines(&mut self, lines: Range<LineIdx>) {
+        let end = min(lines.end, self.height());
+        if lines.start < end {
+            // Removing the last lines removes the line break in front of them
+            let start = match lines.start.checked_sub(1) {
+                Some(above) if end == self.height() => self.text_position(self.line_end(above)),
+                _ => self.line_start_position(lines.start),
+            };
+            let old_end = self.line_start_position(end);
+            self.lines.drain(lines.start..end);
+            self.dirty = true;
+            self.edit_syntax(&TextEdit {
+                start,
+                old_end,
+                new_end: start,
+            });
+            self.bookmarks.remove_lines(lines.start..end);
+            self.folds
+                .lines_replaced(lines.start, end.saturating_sub(lines.start), 0);
+        }
+    }
+
+    // Inserts a copy of the lines right behind them.
+    pub fn duplicate_lines(&mut self, lines: Range<LineIdx>) {
+        let end = min(lines.end, self.height());
+        if let Some(copies) = self.lines.get(lines.start..end).map(<[Line]>::to_vec) {
+            let count = copies.len();
+            let start = self.line_start_position(end);
+            self.lines.splice(end..end, copies);
+            self.dirty = true;
+            let new_end = Location {
+                line_idx: end.saturating_add(count),
+                grapheme_idx: 0,
+            };
+            self.syntax_edited(start, start, new_end);
+            self.bookmarks.insert_lines(end, count);
+            self.folds.lines_replaced(end, 0, count);
         }
     }
+
+    // Swaps the lines with the line above them. Returns false if the lines are
+    // already at the top of the buffer.
+    pub fn move_lines_up(&mut self, lines: Range<LineIdx>) -> bool {
+        let end = min(lines.end, self.height());
+        if lines.start == 0 || lines.start >= end {
+            return false;
+        }
+        let above = lines.start.saturating_sub(1);
+        let (start, old_end) = self.lines_position(above..end);
+        let Some(affected) = self.lines.get_mut(above..end) else {
+            return false;
+        };
+        affected.rotate_left(1);
+        self.dirty = true;
+        self.syntax_edited(start, old_end, self.line_end(end.saturating_sub(1)));
+        let count = end.saturating_sub(above);
+        self.folds.lines_replaced(above, count, count);
+        self.bookmarks.map_lines(|line_idx| {
+            if line_idx == above {
+                end.saturating_sub(1)
+            } else if (lines.start..end).contains(&line_idx) {
+                line_idx.saturating_sub(1)
+            } else {
+                line_idx
+            }
+        });
+        true
+    }
+
+    // Swaps the lines with the line below them. Returns false if the lines are
+    // already at the bottom of the buffer.
+    pub fn move_lines_down(&mut self, lines: Range<LineIdx>) -> bool {
+        if lines.start >= lines.end {
+            return false;
+        }
+        let (start, old_end) = self.lines_position(lines.start..lines.end.saturating_add(1));
+        let Some(affected) = self.lines.get_mut(lines.start..=lines.end) else {
+            return false;
+        };
+        affected.rotate_right(1);
+        self.dirty = true;
+        self.syntax_edited(start, old_end, self.line_end(lines.end));
+        let count = lines.len().saturating_add(1);
+        self.folds.lines_replaced(lines.start, count, count);
+        self.bookmarks.map_lines(|line_idx| {
+            if line_idx == lines.end {
+                lines.start
+            } else if lines.contains(&line_idx) {
+                line_idx.saturating_add(1)
+            } else {
+                line_idx
+            }
+        });
+        true
+    }
+
+    // Appends the next line to the given one. Whitespace around the join is
+    // replaced by a single space, which is left out if either side is empty.
+    // Returns the location of the join, or None if there is no next line.
+    pub fn join_lines(&mut self, idx: LineIdx) -> Option<Location> {
+        let next_idx = idx.saturating_add(1);
+        if next_idx >= self.height() {
+            return None;
+        }
+        let (start, old_end) = self.lines_position(idx..next_idx.saturating_add(1));
+        let next_line = self.lines.remove(next_idx);
+        let line = self.lines.get_mut(idx)?;
+        line.trim_end();
+        let location = Location {
+            line_idx: idx,
+            grapheme_idx: line.grapheme_count(),
+        };
+        let next_text = Line::from(next_line.trim_start());
+        let separator_len = if !line.is_empty() && !next_text.is_empty() {
+            line.append_char(' ');
+            1
+        } else {
+            0
+        };
+        line.append(&next_text);
+        self.dirty = true;
+        self.syntax_edited(start, old_end, self.line_end(idx));
+        let next_text_start = Location {
+            line_idx: next_idx,
+            grapheme_idx: next_line
+                .grapheme_count()
+                .saturating_sub(next_text.grapheme_count()),
+        };
+        self.folds.lines_replaced(idx, 2, 1);
+        // The whitespace around the join counts as replaced by the separator
+        self.bookmarks.replace(
+            location,
+            next_text_start,
+            Location {
+                line_idx: idx,
+                grapheme_idx: location.grapheme_idx.saturating_add(separator_len),
+            },
+        );
+        Some(location)
+    }
+
+    pub fn sort_lines(&mut self, lines: Range<LineIdx>) {
+        let end = min(lines.end, self.height());
+        if lines.start >= end {
+            return;
+        }
+        let (start, old_end) = self.lines_position(lines.start..end);
+        let Some(affected) = self.lines.get_mut(lines.start..end) else {
+            return;
+        };
+        // Keep the original positions, so that bookmarks can follow their lines
+        let mut sorted: Vec<(usize, Line)> = affected.iter().cloned().enumerate().collect();
+        sorted.sort_by(|(_, a), (_, b)| str::cmp(a, b));
+        let mut new_offsets = vec![0; sorted.len()];
+        for (new_offset, (old_offset, line)) in sorted.into_iter().enumerate() {
+            if let Some(slot) = new_offsets.get_mut(old_offset) {
+                *slot = new_offset;
+            }
+            if let Some(slot) = affected.get_mut(new_offset) {
+                *slot = line;
+            }
+        }
+        self.dirty = true;
+        self.syntax_edited(start, old_end, self.line_end(end.saturating_sub(1)));
+        let count = end.saturating_sub(lines.start);
+        self.folds.lines_replaced(lines.start, count, count);
+        self.bookmarks.map_lines(|line_idx| {
+            line_idx
+                .checked_sub(lines.start)
+                .and_then(|offset| new_offsets.get(offset))
+                .map_or(line_idx, |new_offset| {
+                    lines.start.saturating_add(*new_offset)
+                })
+        });
+    }
+
+    // region end
+
+    // region: brackets
+
+    // Returns the bracket under the given location, or else the one in front of
+    // it, together with its matching bracket. Brackets within strings, character
+    // literals and comments are ignored, and the matching bracket is only
+    // searched for within the given lines.
+    pub fn bracket_pair(&mut self, at: Location, within: Range<LineIdx>) -> Option<[Location; 2]> {
+        let end = min(within.end, self.height());
+        self.syntax
+            .highlight_until(&self.lines, max(end.saturating_sub(1), at.line_idx));
+        let line = self.lines.get(at.line_idx)?;
+        let candidates = [Some(at.grapheme_idx), at.grapheme_idx.checked_sub(1)];
+        for grapheme_idx in candidates.into_iter().flatten() {
+            if grapheme_idx >= line.grapheme_count() {
+                continue;
+            }
+            let byte_idx = line.grapheme_idx_to_byte_idx(grapheme_idx);
+            if self.syntax.is_literal_or_comment(at.line_idx, byte_idx) {
+                continue;
+            }
+            let bracket = line.sub_str(grapheme_idx..grapheme_idx.saturating_add(1));
+            let location = Location {
+                line_idx: at.line_idx,
+                grapheme_idx,
+            };
+            for (open, close) in BRACKETS {
+                if bracket == open {
+                    return self
+                        .find_closing_bracket(location, (open, close), end)
+                        .map(|matching| [location, matching]);
+                }
+                if bracket == close {
+                    return self
+                        .find_opening_bracket(location, (open, close), within.start)
+                        .map(|matching| [location, matching]);
+                }
+            }
+        }
+        None
+    }
+
+    // Whether text inserted at the location ends up within a string, a character
+    // literal or a comment.
+    pub fn is_within_literal_or_comment(&mut self, at: Location) -> bool {
+        self.syntax.highlight_until(&self.lines, at.line_idx);
+        let Some(line) = self.lines.get(at.line_idx) else {
+            return false;
+        };
+        let before = at.grapheme_idx.checked_sub(1).and_then(|grapheme_idx| {
+            self.syntax
+                .literal_or_comment_at(at.line_idx, line.grapheme_idx_to_byte_idx(grapheme_idx))
+        });
+        let after = self.syntax.literal_or_comment_at(
+            at.line_idx,
+            line.grapheme_idx_to_byte_idx(min(at.grapheme_idx, line.grapheme_count())),
+        );
+        match (before, after) {
+            (Some(_), Some(_)) => true,
+            // Comments run to the end of the line
+            (Some(AnnotationType::Comment | AnnotationType::DocComment), None) => {
+                at.grapheme_idx >= line.grapheme_count()
+            }
+            // Strings and comments may continue from the previous line
+            (None, Some(_)) => at.grapheme_idx == 0,
+            _ => false,
+        }
+    }
+
+    // Classifies the syntax of the lines up to and including the given one,
+    // unless it is known already.
+    pub fn highlight_syntax_until(&mut self, idx: LineIdx) {
+        self.syntax.highlight_until(&self.lines, idx);
+    }
+
+    pub const fn syntax(&self) -> &Syntax {
+        &self.syntax
+    }
+
+    // Scans forward for the closing bracket, up to the given line. The syntax
+    // must be classified up to there.
+    fn find_closing_bracket(
+        &self,
+        from: Location,
+        (open, close): (&str, &str),
+        end: LineIdx,
+    ) -> Option<Location> {
+        let mut depth: usize = 0;
+        for (line_idx, line) in self.lines.iter().enumerate().take(end).skip(from.line_idx) {
+            for (grapheme_idx, (byte_idx, grapheme)) in line.grapheme_indices(true).enumerate() {
+                if line_idx == from.line_idx && grapheme_idx <= from.grapheme_idx
+                    || self.syntax.is_literal_or_comment(line_idx, byte_idx)
+                {
+                    continue;
+                }
+                if grapheme == open {
+                    depth = depth.saturating_add(1);
+                } else if grapheme == close {
+                    if depth == 0 {
+                        return Some(Location {
+                            line_idx,
+                            grapheme_idx,
+                        });
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+        None
+    }
+
+    // Scans backward for the opening bracket, down to the given line. The syntax
+    // must be classified up to the line containing the closing bracket.
+    fn find_opening_bracket(
+        &self,
+        from: Location,
+        (open, close): (&str, &str),
+        start: LineIdx,
+    ) -> Option<Location> {
+        let mut depth: usize = 0;
+        for (line_idx, line) in self
+            .lines
+            .iter()
+            .enumerate()
+            .take(from.line_idx.saturating_add(1))
+            .skip(start)
+            .rev()
+        {
+            for (grapheme_idx, (byte_idx, grapheme)) in line
+                .grapheme_indices(true)
+                .enumerate()
+                .collect::<Vec<_>>()
+                .into_iter()
+                .rev()
+            {
+                if line_idx == from.line_idx && grapheme_idx >= from.grapheme_idx
+                    || self.syntax.is_literal_or_comment(line_idx, byte_idx)
+                {
+                    continue;
+                }
+                if grapheme == close {
+                    depth = depth.saturating_add(1);
+                } else if grapheme == open {
+                    if depth == 0 {
+                        return Some(Location {
+                            line_idx,
+                            grapheme_idx,
+                        });
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+        None
+    }
+
+    // region end
+
+    // region: folding
+
+    // Returns the regions which can be folded, as the ranges of lines they hide
+    // behind their header line, ordered by their start. Brackets spanning
+    // several lines hide the lines between them. Any other line hides the lines
+    // indented deeper than it which follow it.
+    pub fn fold_regions(&mut self, tab_width: ColIdx) -> Vec<Range<LineIdx>> {
+        let mut regions: BTreeMap<LineIdx, Range<LineIdx>> = BTreeMap::new();
+        for region in self.bracket_fold_regions() {
+            // Of several brackets opened on the same line, the outermost wins
+            let longest = regions
+                .get(&region.start)
+                .is_none_or(|existing| existing.end < region.end);
+            if longest {
+                regions.insert(region.start, region);
+            }
+        }
+        for header in 0..self.height() {
+            let start = header.saturating_add(1);
+            if regions.contains_key(&start) {
+                continue;
+            }
+            if let Some(region) = self.indentation_fold_region(header, tab_width) {
+                regions.insert(start, region);
+            }
+        }
+        regions.into_values().collect()
+    }
+
+    fn bracket_fold_regions(&mut self) -> Vec<Range<LineIdx>> {
+        self.syntax
+            .highlight_until(&self.lines, self.height().saturating_sub(1));
+        // The closing bracket and line of each bracket not closed yet
+        let mut unclosed: Vec<(&str, LineIdx)> = Vec::new();
+        let mut regions = Vec::new();
+        for (line_idx, line) in self.lines.iter().enumerate() {
+            for (byte_idx, grapheme) in line.grapheme_indices(true) {
+                if self.syntax.is_literal_or_comment(line_idx, byte_idx) {
+                    continue;
+                }
+                if let Some((_, close)) = BRACKETS.iter().find(|(open, _)| *open == grapheme) {
+                    unclosed.push((close, line_idx));
+                } else if let Some(idx) = unclosed.iter().rposition(|(close, _)| *close == grapheme)
+                {
+                    if let Some(&(_, open_line_idx)) = unclosed.get(idx) {
+                        let start = open_line_idx.saturating_add(1);
+                        if start < line_idx {
+                            regions.push(start..line_idx);
+                        }
+                    }
+                    unclosed.truncate(idx);
+                }
+            }
+        }
+        regions
+    }
+
+    // Returns the lines following the header which are indented deeper than it,
+    // up to the last non-blank one.
+    fn indentation_fold_region(
+        &self,
+        header: LineIdx,
+        tab_width: ColIdx,
+    ) -> Option<Range<LineIdx>> {
+        let indent_width = |line: &Line| {
+            let first_non_blank = line.first_non_blank();
+            (first_non_blank < line.grapheme_count())
+                .then(|| line.width_until(first_non_blank, tab_width))
+        };
+        let header_indent = indent_width(self.lines.get(header)?)?;
+        let start = header.saturating_add(1);
+        let mut end = start;
+        for (line_idx, line) in self.lines.iter().enumerate().skip(start) {
+            match indent_width(line) {
+                None => {}
+                Some(indent) if indent > header_indent => end = line_idx.saturating_add(1),
+                Some(_) => break,
+            }
+        }
+        (start < end).then_some(start..end)
+    }
+
+    // region end
+
+    // region: word boundaries
+
+    // Returns the start of the next word behind the given location. Empty lines
+    // count as a word of their own.
+    pub fn next_word_start(&self, from: Location) -> Location {
+        let mut location = from;
+        while let Some(line) = self.lines.get(location.line_idx) {
+            let next = line
+                .word_ranges()
+                .into_iter()
+                .map(|range| range.start)
+                .find(|&start| location != from || start > from.grapheme_idx);
+            if let Some(grapheme_idx) = next {
+                return Location {
+                    line_idx: location.line_idx,
+                    grapheme_idx,
+                };
+            }
+            if location != from && line.is_empty() {
+                return location;
+            }
+            location = Location {
+                line_idx: location.line_idx.saturating_add(1),
+                grapheme_idx: 0,
+            };
+        }
+        self.end_location()
+    }
+
+    // Returns the start of the word in front of the given location. Empty lines
+    // count as a word of their own.
+    pub fn prev_word_start(&self, from: Location) -> Location {
+        let mut location = from;
+        loop {
+            if let Some(line) = self.lines.get(location.line_idx) {
+                let prev = line
+                    .word_ranges()
+                    .into_iter()
+                    .map(|range| range.start)
+                    .rfind(|&start| location != from || start < from.grapheme_idx);
+                if let Some(grapheme_idx) = prev {
+                    return Location {
+                        line_idx: location.line_idx,
+                        grapheme_idx,
+                    };
+                }
+                if location != from && line.is_empty() {
+                    return location;
+                }
+            }
+            if location.line_idx == 0 {
+                return Location::default();
+            }
+            location = Location {
+                line_idx: location.line_idx.saturating_sub(1),
+                grapheme_idx: 0,
+            };
+        }
+    }
+
+    // Returns the location of the last grapheme of the word ending behind the
+    // given location.
+    pub fn next_word_end(&self, from: Location) -> Location {
+        let mut location = from;
+        while let Some(line) = self.lines.get(location.line_idx) {
+            let next = line
+                .word_ranges()
+                .into_iter()
+                .map(|range| range.end.saturating_sub(1))
+                .find(|&last| location != from || last > from.grapheme_idx);
+            if let Some(grapheme_idx) = next {
+                return Location {
+                    line_idx: location.line_idx,
+                    grapheme_idx,
+                };
+            }
+            location = Location {
+                line_idx: location.line_idx.saturating_add(1),
+                grapheme_idx: 0,
+            };
+        }
+        self.end_location()
+    }
+
+    // Returns the location behind the last grapheme of the document.
+    pub fn end_location(&self) -> Location {
+        let line_idx = self.height().saturating_sub(1);
+        Location {
+            line_idx,
+            grapheme_idx: self.grapheme_count(line_idx),
+        }
+    }
+
+    // region end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::FileType;
+
+    fn rust_buffer(text: &str) -> Buffer {
+        Buffer {
+            lines: text.lines().map(Line::from).collect(),
+            syntax: Syntax::new(FileType::Rust),
+            ..Buffer::default()
+        }
+    }
+
+    const fn at(line_idx: LineIdx, grapheme_idx: GraphemeIdx) -> Location {
+        Location {
+            line_idx,
+            grapheme_idx,
+        }
+    }
+
+    // Classifies the buffer, then checks that the syntax and line offsets kept
+    // across the edits agree with those of the edited text from scratch.
+    fn assert_syntax_is_current(buffer: &mut Buffer) {
+        #[cfg(feature = "tree-sitter")]
+        {
+            let mut offset: ByteIdx = 0;
+            for line_idx in 0..buffer.height() {
+                assert_eq!(buffer.text_position(at(line_idx, 0)).offset, offset);
+                offset = offset
+                    .saturating_add(buffer.lines[line_idx].len())
+                    .saturating_add(1);
+            }
+        }
+        let last_line_idx = buffer.height().saturating_sub(1);
+        buffer.highlight_syntax_until(last_line_idx);
+        let mut fresh = Syntax::new(FileType::Rust);
+        fresh.highlight_until(&buffer.lines, last_line_idx);
+        for line_idx in 0..buffer.height() {
+            assert_eq!(
+                buffer.syntax().get_annotations(line_idx),
+                fresh.get_annotations(line_idx),
+                "line {line_idx}"
+            );
+        }
+    }
+
+    const SOURCE: &str = "fn main() {\n    let a = \"b\";\n    // c\n    let d = 'e';\n}";
+
+    #[test]
+    fn syntax_follows_character_edits() {
+        let mut buffer = rust_buffer(SOURCE);
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_char('/', at(1, 0));
+        buffer.insert_char('*', at(1, 1));
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_char('é', at(3, 4));
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete(at(1, 0));
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete(at(2, 6));
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_newline(at(1, 5));
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_char('x', at(buffer.height(), 0));
+        assert_syntax_is_current(&mut buffer);
+    }
+
+    #[test]
+    fn syntax_follows_text_edits() {
+        let mut buffer = rust_buffer(SOURCE);
+        assert_syntax_is_current(&mut buffer);
+        let end = buffer.insert_text("/* é\r\n*/ \"", at(1, 4));
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete_range(at(1, 4)..end);
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete_range(at(0, 3)..at(3, 2));
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_text("\nr#\"\n\"#;", at(buffer.height(), 0));
+        assert_syntax_is_current(&mut buffer);
+    }
+
+    #[test]
+    fn syntax_follows_line_operations() {
+        let mut buffer = rust_buffer(SOURCE);
+        assert_syntax_is_current(&mut buffer);
+        buffer.move_lines_up(2..3);
+        assert_syntax_is_current(&mut buffer);
+        buffer.move_lines_down(0..2);
+        assert_syntax_is_current(&mut buffer);
+        buffer.duplicate_lines(3..5);
+        assert_syntax_is_current(&mut buffer);
+        buffer.join_lines(0);
+        assert_syntax_is_current(&mut buffer);
+        buffer.sort_lines(0..4);
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete_lines(4..buffer.height());
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete_lines(0..1);
+        assert_syntax_is_current(&mut buffer);
+        buffer.delete_lines(0..buffer.height());
+        assert_syntax_is_current(&mut buffer);
+        buffer.insert_text("/*\n*/", at(0, 0));
+        assert_syntax_is_current(&mut buffer);
+    }
+
+    #[test]
+    fn syntax_follows_trimmed_whitespace() {
+        let mut buffer = rust_buffer("fn a() {  \n    let b = 1; \t\n}");
+        assert_syntax_is_current(&mut buffer);
+        buffer.trim_trailing_whitespace();
+        assert_syntax_is_current(&mut buffer);
+    }
+
+    #[test]
+    fn bracket_pair_is_searched_within_lines() {
+        let mut buffer = rust_buffer(SOURCE);
+        assert_eq!(
+            buffer.bracket_pair(at(0, 10), 0..5),
+            Some([at(0, 10), at(4, 0)])
+        );
+        assert_eq!(buffer.bracket_pair(at(0, 10), 0..4), None);
+        assert_eq!(buffer.bracket_pair(at(4, 0), 1..5), None);
+    }
+
+    #[test]
+    fn brackets_in_literals_and_comments_are_ignored() {
+        let mut buffer = rust_buffer("f(\")\", ')', // )\n)");
+        assert_eq!(
+            buffer.bracket_pair(at(0, 1), 0..2),
+            Some([at(0, 1), at(1, 0)])
+        );
+    }
+
+    #[test]
+    fn literals_and_comments_are_found_around_the_caret() {
+        let mut buffer = rust_buffer("f(\")\", ')', // )\n)");
+        assert!(buffer.is_within_literal_or_comment(at(0, 3)));
+        assert!(!buffer.is_within_literal_or_comment(at(0, 6)));
+        assert!(buffer.is_within_literal_or_comment(at(0, 16)));
+        buffer.insert_char('/', at(0, 0));
+        buffer.insert_char('/', at(0, 0));
+        assert!(buffer.is_within_literal_or_comment(at(0, 8)));
+    }
 }
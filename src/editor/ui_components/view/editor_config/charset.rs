@@ -0,0 +1,173 @@
+use std::{
+    fmt::{self, Display},
+    io::{Error, ErrorKind},
+};
+
+const BOM: char = '\u{feff}';
+
+// The encoding a file is read and written with.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum Charset {
+    Latin1,
+    #[default]
+    Utf8,
+    // UTF-8 with a leading byte order mark.
+    Utf8Bom,
+    Utf16Be,
+    Utf16Le,
+}
+
+impl Charset {
+    pub fn decode(self, bytes: Vec<u8>) -> Result<String, Error> {
+        match self {
+            Self::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+            Self::Utf8 | Self::Utf8Bom => {
+                let text = String::from_utf8(bytes)
+                    .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+                Ok(match text.strip_prefix(BOM) {
+                    Some(text) if self == Self::Utf8Bom => text.to_string(),
+                    _ => text,
+                })
+            }
+            Self::Utf16Be | Self::Utf16Le => {
+                let units: Vec<u16> = bytes
+                    .chunks(2)
+                    .map(|pair| {
+                        let pair = [
+                            pair.first().copied().unwrap_or_default(),
+                            pair.get(1).copied().unwrap_or_default(),
+                        ];
+                        if self == Self::Utf16Be {
+                            u16::from_be_bytes(pair)
+                        } else {
+                            u16::from_le_bytes(pair)
+                        }
+                    })
+                    .collect();
+                let text = String::from_utf16(&units)
+                    .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+                // The byte order mark is optional for UTF-16
+                Ok(match text.strip_prefix(BOM) {
+                    Some(text) => text.to_string(),
+                    None => text,
+                })
+            }
+        }
+    }
+
+    // Encodes the text. Characters which Latin-1 can't represent are written as
+    // `?`.
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Latin1 => text
+                .chars()
+                .map(|character| u8::try_from(character).unwrap_or(b'?'))
+                .collect(),
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf8Bom => format!("{BOM}{text}").into_bytes(),
+            Self::Utf16Be => format!("{BOM}{text}")
+                .encode_utf16()
+                .flat_map(u16::to_be_bytes)
+                .collect(),
+            Self::Utf16Le => format!("{BOM}{text}")
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&str> for Charset {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "latin1" => Ok(Self::Latin1),
+            "utf-8" => Ok(Self::Utf8),
+            "utf-8-bom" => Ok(Self::Utf8Bom),
+            "utf-16be" => Ok(Self::Utf16Be),
+            "utf-16le" => Ok(Self::Utf16Le),
+            _ => Err(format!("Unknown charset: {value}")),
+        }
+    }
+}
+
+impl Display for Charset {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Latin1 => "latin1",
+            Self::Utf8 => "utf-8",
+            Self::Utf8Bom => "utf-8-bom",
+            Self::Utf16Be => "utf-16be",
+            Self::Utf16Le => "utf-16le",
+        };
+        write!(formatter, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHARSETS: [Charset; 5] = [
+        Charset::Latin1,
+        Charset::Utf8,
+        Charset::Utf8Bom,
+        Charset::Utf16Be,
+        Charset::Utf16Le,
+    ];
+
+    #[test]
+    fn encoded_text_decodes_to_itself() {
+        for charset in CHARSETS {
+            let text = "Grüße\r\n";
+            assert_eq!(
+                charset.decode(charset.encode(text)).ok().as_deref(),
+                Some(text),
+                "{charset}"
+            );
+        }
+        let text = "→ 😀";
+        for charset in CHARSETS.into_iter().skip(1) {
+            assert_eq!(
+                charset.decode(charset.encode(text)).ok().as_deref(),
+                Some(text),
+                "{charset}"
+            );
+        }
+    }
+
+    #[test]
+    fn byte_order_marks_are_written_and_stripped() {
+        assert_eq!(Charset::Utf8Bom.encode("a"), b"\xef\xbb\xbfa");
+        assert_eq!(Charset::Utf16Be.encode("a"), b"\xfe\xff\0a");
+        assert_eq!(Charset::Utf16Le.encode("a"), b"\xff\xfea\0");
+        assert_eq!(
+            Charset::Utf16Le.decode(b"a\0".to_vec()).ok().as_deref(),
+            Some("a")
+        );
+        // Plain UTF-8 keeps a byte order mark as part of the text
+        assert_eq!(
+            Charset::Utf8
+                .decode(b"\xef\xbb\xbfa".to_vec())
+                .ok()
+                .as_deref(),
+            Some("\u{feff}a")
+        );
+    }
+
+    #[test]
+    fn unrepresentable_text_is_not_lost_silently() {
+        assert_eq!(Charset::Latin1.encode("a→b"), b"a?b");
+        assert!(Charset::Utf8.decode(vec![0xff]).is_err());
+        assert!(Charset::Utf16Le.decode(vec![0x00, 0xd8]).is_err());
+    }
+
+    #[test]
+    fn names_parse_to_themselves() {
+        for charset in CHARSETS {
+            assert_eq!(Charset::try_from(charset.to_string().as_str()), Ok(charset));
+        }
+        assert!(Charset::try_from("utf-32").is_err());
+    }
+}
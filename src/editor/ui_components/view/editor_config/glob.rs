@@ -0,0 +1,275 @@
+// Matching of EditorConfig section globs. Supports `*`, `**`, `?`, `[seq]`,
+// `[!seq]`, `{s1,s2}`, `{num1..num2}` and `\` escapes.
+#[derive(Clone, Debug)]
+enum Token {
+    Literal(char),
+    // `?`: any single character except `/`
+    AnyChar,
+    // `*`: any sequence of characters except `/`
+    Star,
+    // `**`: any sequence of characters
+    DoubleStar,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Alternatives(Vec<Vec<Token>>),
+    NumberRange(i64, i64),
+}
+
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let chars: Vec<char> = pattern.chars().collect();
+        Self {
+            tokens: parse(&chars),
+        }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let chars: Vec<char> = path.chars().collect();
+        matches(&self.tokens, &chars)
+    }
+}
+
+fn parse(chars: &[char]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while let Some(&character) = chars.get(idx) {
+        idx = idx.saturating_add(1);
+        match character {
+            '\\' => {
+                if let Some(&escaped) = chars.get(idx) {
+                    tokens.push(Token::Literal(escaped));
+                    idx = idx.saturating_add(1);
+                } else {
+                    tokens.push(Token::Literal('\\'));
+                }
+            }
+            '*' if chars.get(idx) == Some(&'*') => {
+                tokens.push(Token::DoubleStar);
+                idx = idx.saturating_add(1);
+            }
+            '*' => tokens.push(Token::Star),
+            '?' => tokens.push(Token::AnyChar),
+            '[' => match parse_class(chars, idx) {
+                Some((token, end)) => {
+                    tokens.push(token);
+                    idx = end;
+                }
+                None => tokens.push(Token::Literal('[')),
+            },
+            '{' => match closing_brace(chars, idx) {
+                Some(end) => {
+                    let content = chars.get(idx..end).unwrap_or_default();
+                    tokens.extend(parse_braces(content));
+                    idx = end.saturating_add(1);
+                }
+                None => tokens.push(Token::Literal('{')),
+            },
+            _ => tokens.push(Token::Literal(character)),
+        }
+    }
+    tokens
+}
+
+// Parses the character class starting behind `[`, returning the token and the
+// index behind the closing `]`.
+fn parse_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let end = chars
+        .get(start..)?
+        .iter()
+        .position(|&character| character == ']' || character == '/')
+        .map(|offset| start.saturating_add(offset))
+        .filter(|&end| chars.get(end) == Some(&']'))?;
+    let mut content = chars.get(start..end)?;
+    let negated = content.first() == Some(&'!');
+    if negated {
+        content = content.get(1..)?;
+    }
+    let mut ranges = Vec::new();
+    let mut idx = 0;
+    while let Some(&first) = content.get(idx) {
+        match (
+            content.get(idx.saturating_add(1)),
+            content.get(idx.saturating_add(2)),
+        ) {
+            (Some('-'), Some(&last)) => {
+                ranges.push((first, last));
+                idx = idx.saturating_add(3);
+            }
+            _ => {
+                ranges.push((first, first));
+                idx = idx.saturating_add(1);
+            }
+        }
+    }
+    Some((Token::Class { negated, ranges }, end.saturating_add(1)))
+}
+
+// Returns the index of the `}` matching the `{` before `start`.
+fn closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut idx = start;
+    while let Some(&character) = chars.get(idx) {
+        match character {
+            '\\' => idx = idx.saturating_add(1),
+            '{' => depth = depth.saturating_add(1),
+            '}' if depth == 0 => return Some(idx),
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        idx = idx.saturating_add(1);
+    }
+    None
+}
+
+// Parses the content of a brace expression, which is either a number range, a
+// comma separated list of alternatives, or literal text.
+fn parse_braces(content: &[char]) -> Vec<Token> {
+    let text: String = content.iter().collect();
+    if let Some((from, to)) = text.split_once("..") {
+        if let (Ok(from), Ok(to)) = (from.parse(), to.parse()) {
+            return vec![Token::NumberRange(from, to)];
+        }
+    }
+    let alternatives = split_alternatives(content);
+    if alternatives.len() > 1 {
+        return vec![Token::Alternatives(
+            alternatives.into_iter().map(parse).collect(),
+        )];
+    }
+    let mut tokens = vec![Token::Literal('{')];
+    tokens.extend(parse(content));
+    tokens.push(Token::Literal('}'));
+    tokens
+}
+
+fn split_alternatives(content: &[char]) -> Vec<&[char]> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    let mut idx = 0;
+    while let Some(&character) = content.get(idx) {
+        match character {
+            '\\' => idx = idx.saturating_add(1),
+            '{' => depth = depth.saturating_add(1),
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                alternatives.push(content.get(start..idx).unwrap_or_default());
+                start = idx.saturating_add(1);
+            }
+            _ => {}
+        }
+        idx = idx.saturating_add(1);
+    }
+    alternatives.push(content.get(start..).unwrap_or_default());
+    alternatives
+}
+
+fn matches(tokens: &[Token], text: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match token {
+        Token::Literal(expected) => match text.split_first() {
+            Some((character, text)) => character == expected && matches(rest, text),
+            None => false,
+        },
+        Token::AnyChar => match text.split_first() {
+            Some((character, text)) => *character != '/' && matches(rest, text),
+            None => false,
+        },
+        Token::Class { negated, ranges } => match text.split_first() {
+            Some((character, text)) => {
+                let in_class = ranges
+                    .iter()
+                    .any(|(first, last)| (first..=last).contains(&character));
+                *character != '/' && in_class != *negated && matches(rest, text)
+            }
+            None => false,
+        },
+        Token::Star => {
+            let segment_end = text
+                .iter()
+                .position(|&character| character == '/')
+                .unwrap_or(text.len());
+            (0..=segment_end).any(|idx| matches(rest, text.get(idx..).unwrap_or_default()))
+        }
+        Token::DoubleStar => {
+            (0..=text.len()).any(|idx| matches(rest, text.get(idx..).unwrap_or_default()))
+        }
+        Token::Alternatives(alternatives) => alternatives.iter().any(|alternative| {
+            let mut tokens = alternative.clone();
+            tokens.extend_from_slice(rest);
+            matches(&tokens, text)
+        }),
+        Token::NumberRange(from, to) => (1..=text.len()).any(|len| {
+            let (number, text) = text.split_at(len);
+            number
+                .iter()
+                .collect::<String>()
+                .parse::<i64>()
+                .is_ok_and(|number| (*from..=*to).contains(&number))
+                && matches(rest, text)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternatives_match_any_of_their_patterns() {
+        let glob = Glob::new("*.{rs,toml}");
+        assert!(glob.matches("main.rs"));
+        assert!(glob.matches("Cargo.toml"));
+        assert!(!glob.matches("README.md"));
+        assert!(Glob::new("{a,{b,c}}x").matches("cx"));
+        assert!(Glob::new("{single}").matches("{single}"));
+    }
+
+    #[test]
+    fn number_ranges_match_numbers_within_them() {
+        let glob = Glob::new("file{1..3}.txt");
+        assert!(glob.matches("file1.txt"));
+        assert!(glob.matches("file3.txt"));
+        assert!(!glob.matches("file0.txt"));
+        assert!(!glob.matches("file4.txt"));
+        assert!(!glob.matches("filex.txt"));
+        assert!(Glob::new("{-2..2}").matches("-1"));
+    }
+
+    #[test]
+    fn double_stars_cross_directories() {
+        let glob = Glob::new("src/**/*.rs");
+        assert!(glob.matches("src/editor/mod.rs"));
+        assert!(glob.matches("src/a/b/c.rs"));
+        assert!(!glob.matches("tests/a.rs"));
+        assert!(Glob::new("**.rs").matches("a/b.rs"));
+        assert!(!Glob::new("*.rs").matches("a/b.rs"));
+    }
+
+    #[test]
+    fn classes_match_single_characters() {
+        assert!(Glob::new("[ab].rs").matches("a.rs"));
+        assert!(!Glob::new("[ab].rs").matches("c.rs"));
+        assert!(Glob::new("[a-c]").matches("b"));
+        assert!(Glob::new("[!x].rs").matches("a.rs"));
+        assert!(!Glob::new("[!x].rs").matches("x.rs"));
+        assert!(!Glob::new("a[!x]b").matches("a/b"));
+        assert!(Glob::new("[ab").matches("[ab"));
+    }
+
+    #[test]
+    fn escaped_characters_are_literal() {
+        assert!(Glob::new("\\*.rs").matches("*.rs"));
+        assert!(!Glob::new("\\*.rs").matches("a.rs"));
+        assert!(Glob::new("?.rs").matches("a.rs"));
+        assert!(!Glob::new("?.rs").matches("ab.rs"));
+    }
+}
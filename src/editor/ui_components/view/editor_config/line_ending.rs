@@ -0,0 +1,42 @@
+use std::fmt::{self, Display};
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+}
+
+impl TryFrom<&str> for LineEnding {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "lf" => Ok(Self::Lf),
+            "crlf" => Ok(Self::CrLf),
+            "cr" => Ok(Self::Cr),
+            _ => Err(format!("Unknown line ending: {value}")),
+        }
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lf => write!(formatter, "lf"),
+            Self::CrLf => write!(formatter, "crlf"),
+            Self::Cr => write!(formatter, "cr"),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    fmt::{self, Display},
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use super::super::super::Indentation;
+use crate::prelude::*;
+
+mod charset;
+mod glob;
+mod line_ending;
+
+pub use charset::Charset;
+use glob::Glob;
+pub use line_ending::LineEnding;
+
+const FILE_NAME: &str = ".editorconfig";
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndentStyle {
+    Space,
+    Tab,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum IndentSize {
+    Columns(ColIdx),
+    // Indent by the tab width.
+    Tab,
+}
+
+// The EditorConfig properties applying to a file. Properties which are not set
+// by any `.editorconfig` file keep the editor's own settings.
+// See https://editorconfig.org for the file format.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct EditorConfig {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<IndentSize>,
+    pub tab_width: Option<ColIdx>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<Charset>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+}
+
+impl EditorConfig {
+    // Collects the properties for the given file from the `.editorconfig` files
+    // in its directory and all parent directories, up to the first file
+    // declaring `root = true`. Closer files take precedence.
+    pub fn for_file(path: &Path) -> Self {
+        let mut config = Self::default();
+        let Ok(path) = absolute_path(path) else {
+            return config;
+        };
+        let mut files = Vec::new();
+        for directory in path.ancestors().skip(1) {
+            let Ok(contents) = read_to_string(directory.join(FILE_NAME)) else {
+                continue;
+            };
+            let file = ConfigFile::parse(&contents);
+            let is_root = file.is_root;
+            files.push((directory, file));
+            if is_root {
+                break;
+            }
+        }
+        for (directory, file) in files.iter().rev() {
+            let Ok(relative_path) = path.strip_prefix(directory) else {
+                continue;
+            };
+            // EditorConfig globs always use forward slashes
+            let relative_path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            file.apply_to(&relative_path, &mut config);
+        }
+        config
+    }
+
+    // Sets a property. Unknown properties and invalid values are ignored, as
+    // the specification requires.
+    fn set(&mut self, key: &str, value: &str) {
+        let unset = value == "unset";
+        match key {
+            "indent_style" => {
+                self.indent_style = match value {
+                    "space" => Some(IndentStyle::Space),
+                    "tab" => Some(IndentStyle::Tab),
+                    _ if unset => None,
+                    _ => return,
+                };
+            }
+            "indent_size" => {
+                self.indent_size = match value {
+                    "tab" => Some(IndentSize::Tab),
+                    _ if unset => None,
+                    _ => match parse_width(value) {
+                        Some(columns) => Some(IndentSize::Columns(columns)),
+                        None => return,
+                    },
+                };
+            }
+            "tab_width" => {
+                if unset {
+                    self.tab_width = None;
+                } else if let Some(tab_width) = parse_width(value) {
+                    self.tab_width = Some(tab_width);
+                }
+            }
+            "end_of_line" => {
+                if unset {
+                    self.end_of_line = None;
+                } else if let Ok(end_of_line) = LineEnding::try_from(value) {
+                    self.end_of_line = Some(end_of_line);
+                }
+            }
+            "charset" => {
+                if unset {
+                    self.charset = None;
+                } else if let Ok(charset) = Charset::try_from(value) {
+                    self.charset = Some(charset);
+                }
+            }
+            "trim_trailing_whitespace" => {
+                if unset {
+                    self.trim_trailing_whitespace = None;
+                } else if let Some(value) = parse_bool(value) {
+                    self.trim_trailing_whitespace = Some(value);
+                }
+            }
+            "insert_final_newline" => {
+                if unset {
+                    self.insert_final_newline = None;
+                } else if let Some(value) = parse_bool(value) {
+                    self.insert_final_newline = Some(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Returns the given indentation with the indentation properties applied.
+    pub fn apply_to(&self, indentation: Indentation) -> Indentation {
+        let mut indentation = indentation;
+        if let Some(indent_style) = self.indent_style {
+            indentation.soft_tabs = indent_style == IndentStyle::Space;
+        }
+        // `tab_width` defaults to a numeric `indent_size`
+        match (self.tab_width, self.indent_size) {
+            (Some(tab_width), _) | (None, Some(IndentSize::Columns(tab_width))) => {
+                indentation.tab_width = tab_width;
+            }
+            _ => {}
+        }
+        // `indent_size` defaults to the tab width for tab indentation
+        match self.indent_size {
+            Some(IndentSize::Columns(columns)) => indentation.width = columns,
+            Some(IndentSize::Tab) => indentation.width = indentation.tab_width,
+            None if self.indent_style == Some(IndentStyle::Tab) => {
+                indentation.width = indentation.tab_width;
+            }
+            None => {}
+        }
+        indentation
+    }
+}
+
+// A parsed `.editorconfig` file.
+struct ConfigFile {
+    is_root: bool,
+    // The glob of each section with its properties, in file order.
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl ConfigFile {
+    fn parse(contents: &str) -> Self {
+        let mut file = Self {
+            is_root: false,
+            sections: Vec::new(),
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                file.sections.push((glob.to_string(), Vec::new()));
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+            match file.sections.last_mut() {
+                Some((_, properties)) => properties.push((key, value)),
+                None if key == "root" => file.is_root = value == "true",
+                None => {}
+            }
+        }
+        file
+    }
+
+    // Applies the properties of all sections matching the path, which is
+    // relative to the directory of this file.
+    fn apply_to(&self, relative_path: &str, config: &mut EditorConfig) {
+        for (glob, properties) in &self.sections {
+            // Globs without a slash match the file name in any directory
+            let matches = if glob.contains('/') {
+                Glob::new(glob.trim_start_matches('/')).matches(relative_path)
+            } else {
+                let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+                Glob::new(glob).matches(file_name)
+            };
+            if matches {
+                for (key, value) in properties {
+                    config.set(key, value);
+                }
+            }
+        }
+    }
+}
+
+// The effective file settings, in EditorConfig notation.
+pub struct FileSettings<'a> {
+    pub indentation: Indentation,
+    pub editor_config: &'a EditorConfig,
+}
+
+impl Display for FileSettings<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Indentation {
+            soft_tabs,
+            width,
+            tab_width,
+            ..
+        } = self.indentation;
+        let indent_style = if soft_tabs { "space" } else { "tab" };
+        let config = self.editor_config;
+        write!(
+            formatter,
+            "indent_style={indent_style} indent_size={width} tab_width={tab_width} end_of_line={} \
+             charset={} trim_trailing_whitespace={} insert_final_newline={}",
+            config.end_of_line.unwrap_or_default(),
+            config.charset.unwrap_or_default(),
+            config.trim_trailing_whitespace.unwrap_or(false),
+            config.insert_final_newline.unwrap_or(true),
+        )
+    }
+}
+
+fn absolute_path(path: &Path) -> std::io::Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        std::env::current_dir().map(|directory| directory.join(path))
+    }
+}
+
+fn parse_width(value: &str) -> Option<ColIdx> {
+    value.parse().ok().filter(|&width| width > 0)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, remove_dir_all, write};
+
+    use super::*;
+
+    fn config_for(contents: &str, relative_path: &str) -> EditorConfig {
+        let mut config = EditorConfig::default();
+        ConfigFile::parse(contents).apply_to(relative_path, &mut config);
+        config
+    }
+
+    #[test]
+    fn later_sections_take_precedence() {
+        let contents = "[*]\nindent_style = space\nindent_size = 4\n\n[*.md]\nindent_size = 2\n";
+        let config = config_for(contents, "docs/README.md");
+        assert_eq!(config.indent_style, Some(IndentStyle::Space));
+        assert_eq!(config.indent_size, Some(IndentSize::Columns(2)));
+        let config = config_for(contents, "src/main.rs");
+        assert_eq!(config.indent_size, Some(IndentSize::Columns(4)));
+    }
+
+    #[test]
+    fn sections_with_a_slash_match_the_relative_path() {
+        let contents = "[src/*.rs]\ntab_width = 8\n[/Makefile]\nindent_style = tab\n";
+        assert_eq!(config_for(contents, "src/main.rs").tab_width, Some(8));
+        assert_eq!(config_for(contents, "main.rs").tab_width, None);
+        assert_eq!(config_for(contents, "src/a/main.rs").tab_width, None);
+        assert_eq!(
+            config_for(contents, "Makefile").indent_style,
+            Some(IndentStyle::Tab)
+        );
+        assert_eq!(config_for(contents, "src/Makefile").indent_style, None);
+    }
+
+    #[test]
+    fn properties_are_unset_or_ignored() {
+        let contents = concat!(
+            "[*]\nCharset = UTF-8\nend_of_line = lf\ntab_width = 0\n",
+            "[*.txt]\ncharset = unset\nend_of_line = other\n",
+        );
+        let config = config_for(contents, "a.txt");
+        assert_eq!(config.charset, None);
+        assert_eq!(config.end_of_line, Some(LineEnding::Lf));
+        assert_eq!(config.tab_width, None);
+        assert_eq!(config_for(contents, "a.rs").charset, Some(Charset::Utf8));
+    }
+
+    #[test]
+    fn root_is_only_declared_before_the_first_section() {
+        assert!(ConfigFile::parse("root = true\n[*]\n").is_root);
+        assert!(!ConfigFile::parse("root = false\n[*]\n").is_root);
+        assert!(!ConfigFile::parse("[*]\nroot = true\n").is_root);
+    }
+
+    #[test]
+    fn closer_files_take_precedence_up_to_the_root() -> std::io::Result<()> {
+        let directory = std::env::temp_dir().join(format!("editorconfig-{}", std::process::id()));
+        let project = directory.join("project");
+        let nested = project.join("nested");
+        create_dir_all(&nested)?;
+        write(directory.join(FILE_NAME), "[*]\ncharset = latin1\n")?;
+        write(
+            project.join(FILE_NAME),
+            "root = true\n[*]\nindent_size = 4\ntab_width = 8\n",
+        )?;
+        write(nested.join(FILE_NAME), "[*.rs]\nindent_size = 2\n")?;
+        let config = EditorConfig::for_file(&nested.join("main.rs"));
+        let other = EditorConfig::for_file(&nested.join("main.c"));
+        remove_dir_all(&directory)?;
+        assert_eq!(config.indent_size, Some(IndentSize::Columns(2)));
+        assert_eq!(config.tab_width, Some(8));
+        assert_eq!(config.charset, None);
+        assert_eq!(other.indent_size, Some(IndentSize::Columns(4)));
+        Ok(())
+    }
+}
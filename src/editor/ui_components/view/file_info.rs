@@ -14,14 +14,7 @@ pub struct FileInfo {
 impl FileInfo {
     pub fn from(file_name: &str) -> Self {
         let path = PathBuf::from(file_name);
-        let file_type = if path
-            .extension()
-            .map_or(false, |ext| ext.eq_ignore_ascii_case("rs"))
-        {
-            FileType::Rust
-        } else {
-            FileType::Text
-        };
+        let file_type = FileType::from_path(&path);
         Self {
             path: Some(path),
             file_type,
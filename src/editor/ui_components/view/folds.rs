@@ -0,0 +1,123 @@
+use std::{mem::take, ops::Range};
+
+use crate::prelude::*;
+
+// The closed folds of a buffer. Each fold hides a range of lines, which is
+// preceded by the fold's header line. Folds may be nested, in which case the
+// hidden lines of the inner fold are part of those of the outer one.
+#[derive(Default)]
+pub struct Folds {
+    // The hidden lines of each fold, ordered by their start.
+    ranges: Vec<Range<LineIdx>>,
+}
+
+impl Folds {
+    pub fn close(&mut self, range: Range<LineIdx>) {
+        if range.start > 0 && range.start < range.end && !self.ranges.contains(&range) {
+            self.ranges.push(range);
+            self.ranges.sort_by_key(|range| range.start);
+        }
+    }
+
+    // Opens the fold with the given header line. Returns false if there is none.
+    pub fn open(&mut self, header: LineIdx) -> bool {
+        let before = self.ranges.len();
+        self.ranges
+            .retain(|range| range.start != header.saturating_add(1));
+        self.ranges.len() != before
+    }
+
+    // Opens all folds hiding the given line. Returns false if there are none.
+    pub fn reveal(&mut self, line_idx: LineIdx) -> bool {
+        let before = self.ranges.len();
+        self.ranges.retain(|range| !range.contains(&line_idx));
+        self.ranges.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    pub fn is_closed(&self, header: LineIdx) -> bool {
+        self.ranges
+            .iter()
+            .any(|range| range.start == header.saturating_add(1))
+    }
+
+    pub fn is_hidden(&self, line_idx: LineIdx) -> bool {
+        self.ranges.iter().any(|range| range.contains(&line_idx))
+    }
+
+    // Returns the number of lines hidden behind the given line, if it is the
+    // header of a closed fold which is itself visible.
+    pub fn hidden_after(&self, line_idx: LineIdx) -> Option<usize> {
+        let next = line_idx.saturating_add(1);
+        self.hidden_ranges()
+            .into_iter()
+            .find(|range| range.start == next)
+            .map(|range| range.len())
+    }
+
+    // region: visible rows
+
+    // Returns the row the line is drawn in, counting only visible lines. Hidden
+    // lines map to the row of the line following their fold.
+    pub fn row_of(&self, line_idx: LineIdx) -> RowIdx {
+        let hidden: usize = self
+            .hidden_ranges()
+            .into_iter()
+            .take_while(|range| range.start < line_idx)
+            .map(|range| range.end.min(line_idx).saturating_sub(range.start))
+            .sum();
+        line_idx.saturating_sub(hidden)
+    }
+
+    // Returns the line drawn in the given row. Rows behind the last line map to
+    // lines behind the end of the buffer.
+    pub fn line_at(&self, row: RowIdx) -> LineIdx {
+        let mut line_idx = row;
+        for range in self.hidden_ranges() {
+            if range.start > line_idx {
+                break;
+            }
+            line_idx = line_idx.saturating_add(range.len());
+        }
+        line_idx
+    }
+
+    // Returns the hidden lines as ordered, non-overlapping ranges.
+    fn hidden_ranges(&self) -> Vec<Range<LineIdx>> {
+        let mut merged: Vec<Range<LineIdx>> = Vec::new();
+        for range in &self.ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range.clone()),
+            }
+        }
+        merged
+    }
+
+    // region end
+
+    // Follows the replacement of `removed` lines starting at `at` by `inserted`
+    // lines. Folds in front of the replaced lines stay, those behind them move
+    // along, and those overlapping them are opened.
+    pub fn lines_replaced(&mut self, at: LineIdx, removed: usize, inserted: usize) {
+        let removed_end = at.saturating_add(removed);
+        self.ranges = take(&mut self.ranges)
+            .into_iter()
+            .filter_map(|range| {
+                if range.end <= at {
+                    Some(range)
+                } else if range.start >= removed_end {
+                    let shift = |line_idx: LineIdx| {
+                        line_idx.saturating_sub(removed).saturating_add(inserted)
+                    };
+                    Some(shift(range.start)..shift(range.end))
+                } else {
+                    None
+                }
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,33 @@
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights the line the caret is on.
+pub struct CurrentLineHighlighter {
+    line_idx: LineIdx,
+    highlights: Vec<Annotation>,
+}
+
+impl CurrentLineHighlighter {
+    pub const fn new(line_idx: LineIdx) -> Self {
+        Self {
+            line_idx,
+            highlights: Vec::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for CurrentLineHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        if idx == self.line_idx {
+            self.highlights = vec![Annotation {
+                annotation_type: AnnotationType::CurrentLine,
+                start: 0,
+                end: line.len(),
+            }];
+        }
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        (idx == self.line_idx).then_some(&self.highlights)
+    }
+}
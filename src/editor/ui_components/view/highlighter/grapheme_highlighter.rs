@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights the graphemes at the given locations, such as a pair of matching
+// brackets or secondary carets.
+pub struct GraphemeHighlighter {
+    locations: Vec<Location>,
+    annotation_type: AnnotationType,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl GraphemeHighlighter {
+    pub fn new(locations: Vec<Location>, annotation_type: AnnotationType) -> Self {
+        Self {
+            locations,
+            annotation_type,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for GraphemeHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        for location in self.locations.iter().filter(|location| {
+            location.line_idx == idx && location.grapheme_idx < line.grapheme_count()
+        }) {
+            self.highlights.entry(idx).or_default().push(Annotation {
+                annotation_type: self.annotation_type,
+                start: line.grapheme_idx_to_byte_idx(location.grapheme_idx),
+                end: line.grapheme_idx_to_byte_idx(location.grapheme_idx.saturating_add(1)),
+            });
+        }
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
@@ -0,0 +1,212 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Annotation, AnnotationType, Line, SequentialHighlighter, SyntaxHighlighter};
+use crate::{editor::language::Language, prelude::*};
+
+// Highlights a language according to its definition file.
+pub struct LanguageSyntaxHighlighter {
+    language: &'static Language,
+    highlights: Vec<Vec<Annotation>>,
+    state: LineState,
+    // The state each highlighted line started in.
+    line_states: Vec<LineState>,
+}
+
+// What a line continues from the lines before it.
+#[derive(Default, Clone, Copy)]
+struct LineState {
+    in_block_comment: bool,
+    // The delimiter of the multiline string the previous line ended in.
+    open_string: Option<&'static str>,
+}
+
+impl LanguageSyntaxHighlighter {
+    pub const fn new(language: &'static Language) -> Self {
+        Self {
+            language,
+            highlights: Vec::new(),
+            state: LineState {
+                in_block_comment: false,
+                open_string: None,
+            },
+            line_states: Vec::new(),
+        }
+    }
+
+    // Continues a block comment or a multiline string from the previous line.
+    fn initial_annotation(&mut self, line: &str) -> Option<Annotation> {
+        if let Some(delimiter) = self.state.open_string {
+            Some(self.close_string(line, 0, delimiter, true))
+        } else if self.state.in_block_comment {
+            Some(self.close_block_comment(line, 0))
+        } else {
+            None
+        }
+    }
+
+    // Annotates a block comment up to and including its closing marker, which
+    // is searched for from the given index on.
+    fn close_block_comment(&mut self, string: &str, from: ByteIdx) -> Annotation {
+        let close = self.language.block_comment().map_or("", |(_, close)| close);
+        let close_idx = string
+            .get(from..)
+            .and_then(|rest| rest.find(close))
+            .map(|idx| idx.saturating_add(from));
+        self.state.in_block_comment = close_idx.is_none();
+        Annotation {
+            annotation_type: AnnotationType::Comment,
+            start: 0,
+            end: close_idx.map_or(string.len(), |idx| idx.saturating_add(close.len())),
+        }
+    }
+
+    // Annotates a string up to and including its closing delimiter, which is
+    // searched for from the given index on. Unterminated strings run to the
+    // end of the line, and multiline ones continue on the next line.
+    fn close_string(
+        &mut self,
+        string: &str,
+        from: ByteIdx,
+        delimiter: &'static str,
+        multiline: bool,
+    ) -> Annotation {
+        let rest = string.get(from..).unwrap_or_default();
+        let mut chars = rest.char_indices();
+        while let Some((idx, char)) = chars.next() {
+            if Some(char) == self.language.escape() {
+                chars.next(); // Skip the escaped character.
+                continue;
+            }
+            if rest
+                .get(idx..)
+                .is_some_and(|tail| tail.starts_with(delimiter))
+            {
+                self.state.open_string = None;
+                return Annotation {
+                    annotation_type: AnnotationType::String,
+                    start: 0,
+                    end: from.saturating_add(idx).saturating_add(delimiter.len()),
+                };
+            }
+        }
+        self.state.open_string = multiline.then_some(delimiter);
+        Annotation {
+            annotation_type: AnnotationType::String,
+            start: 0,
+            end: string.len(),
+        }
+    }
+
+    fn annotate_remainder(&mut self, remainder: &str, at_line_start: bool) -> Option<Annotation> {
+        let language = self.language;
+        if at_line_start && find_prefix(language.line_markers(), remainder).is_some() {
+            return Some(annotate_line(remainder, AnnotationType::KeyWord));
+        }
+        if let Some((open, _)) = language.block_comment() {
+            if remainder.starts_with(open) {
+                return Some(self.close_block_comment(remainder, open.len()));
+            }
+        }
+        if find_prefix(language.line_comments(), remainder).is_some() {
+            return Some(annotate_line(remainder, AnnotationType::Comment));
+        }
+        // Multiline delimiters come first, so that `"""` is not taken for an
+        // empty string followed by a quote.
+        if let Some(delimiter) = find_prefix(language.multiline_strings(), remainder) {
+            return Some(self.close_string(remainder, delimiter.len(), delimiter, true));
+        }
+        if let Some(delimiter) = find_prefix(language.strings(), remainder) {
+            return Some(self.close_string(remainder, delimiter.len(), delimiter, false));
+        }
+        annotate_word(language, remainder)
+    }
+}
+
+impl SyntaxHighlighter for LanguageSyntaxHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        debug_assert_eq!(idx, self.highlights.len());
+        self.line_states.push(self.state);
+        let mut result = Vec::new();
+        let mut iterator = line.split_word_bound_indices().peekable();
+        if let Some(annotation) = self.initial_annotation(line) {
+            result.push(annotation);
+
+            while let Some(&(next_idx, _)) = iterator.peek() {
+                if next_idx >= annotation.end {
+                    break;
+                }
+                iterator.next();
+            }
+        }
+        while let Some((start_idx, _)) = iterator.next() {
+            let Some(remainder) = line.get(start_idx..) else {
+                break;
+            };
+            let at_line_start = line
+                .get(..start_idx)
+                .is_some_and(|before| before.trim_start().is_empty());
+            if let Some(mut annotation) = self.annotate_remainder(remainder, at_line_start) {
+                annotation.shift(start_idx);
+                result.push(annotation);
+
+                while let Some(&(next_idx, _)) = iterator.peek() {
+                    if next_idx >= annotation.end {
+                        break;
+                    }
+                    iterator.next();
+                }
+            }
+        }
+        self.highlights.push(result);
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(idx)
+    }
+}
+
+impl SequentialHighlighter for LanguageSyntaxHighlighter {
+    fn truncate(&mut self, idx: LineIdx) {
+        if let Some(&state) = self.line_states.get(idx) {
+            self.state = state;
+            self.line_states.truncate(idx);
+            self.highlights.truncate(idx);
+        }
+    }
+}
+
+// Returns the first of the markers the string starts with.
+fn find_prefix(markers: &'static [String], string: &str) -> Option<&'static str> {
+    markers
+        .iter()
+        .map(String::as_str)
+        .find(|marker| string.starts_with(marker))
+}
+
+fn annotate_line(string: &str, annotation_type: AnnotationType) -> Annotation {
+    Annotation {
+        annotation_type,
+        start: 0,
+        end: string.len(),
+    }
+}
+
+fn annotate_word(language: &Language, string: &str) -> Option<Annotation> {
+    let word = string.split_word_bounds().next()?;
+    let annotation_type = if language.is_number(word) {
+        AnnotationType::Number
+    } else if language.is_keyword(word) {
+        AnnotationType::KeyWord
+    } else if language.is_type(word) {
+        AnnotationType::Type
+    } else if language.is_known_value(word) {
+        AnnotationType::KnownValue
+    } else {
+        return None;
+    };
+    Some(Annotation {
+        annotation_type,
+        start: 0,
+        end: word.len(),
+    })
+}
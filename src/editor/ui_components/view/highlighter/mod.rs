@@ -1,46 +1,134 @@
+use std::ops::Range;
+
 use super::super::super::{Annotation, AnnotationType, FileType, Line};
 use crate::prelude::*;
 
+mod current_line_highlighter;
+mod grapheme_highlighter;
+mod language_syntax_highlighter;
+mod ruler_highlighter;
 mod rust_syntax_highlighter;
 mod search_result_highlighter;
+mod selection_highlighter;
+mod syntax;
 mod syntax_highlighter;
+#[cfg(feature = "tree-sitter")]
+mod tree_sitter_highlighter;
+mod whitespace_highlighter;
+mod word_highlighter;
 
+use current_line_highlighter::CurrentLineHighlighter;
+use grapheme_highlighter::GraphemeHighlighter;
+use language_syntax_highlighter::LanguageSyntaxHighlighter;
+use ruler_highlighter::RulerHighlighter;
 use rust_syntax_highlighter::RustSyntaxHighlighter;
 use search_result_highlighter::SearchResultHighlighter;
-use syntax_highlighter::SyntaxHighlighter;
-
-fn create_syntax_highlighter(file_type: FileType) -> Option<Box<dyn SyntaxHighlighter>> {
-    match file_type {
-        FileType::Rust => Some(Box::<RustSyntaxHighlighter>::default()),
-        FileType::Text => None,
-    }
-}
+use selection_highlighter::SelectionHighlighter;
+pub use syntax::{Syntax, TextEdit, TextPosition};
+use syntax_highlighter::{SequentialHighlighter, SyntaxHighlighter};
+#[cfg(feature = "tree-sitter")]
+use tree_sitter_highlighter::SyntaxTree;
+use whitespace_highlighter::WhitespaceHighlighter;
+use word_highlighter::WordHighlighter;
 
 #[derive(Default)]
 pub struct Highlighter<'a> {
-    syntax_highlighter: Option<Box<dyn SyntaxHighlighter>>,
+    current_line_highlighter: Option<CurrentLineHighlighter>,
+    syntax: Option<&'a Syntax>,
+    whitespace_highlighter: Option<WhitespaceHighlighter>,
+    ruler_highlighter: Option<RulerHighlighter>,
+    occurrence_highlighter: Option<WordHighlighter>,
     search_result_highlighter: Option<SearchResultHighlighter<'a>>,
+    bracket_highlighter: Option<GraphemeHighlighter>,
+    caret_highlighter: Option<GraphemeHighlighter>,
+    selection_highlighter: Option<SelectionHighlighter>,
 }
 
 impl<'a> Highlighter<'a> {
     pub fn new(
         matched_word: Option<&'a str>,
         selected_match: Option<Location>,
-        file_type: FileType,
+        occurrences_of: Option<String>,
+        selection: Vec<Range<Location>>,
+        brackets: Option<[Location; 2]>,
+        secondary_carets: &[Location],
+        syntax: &'a Syntax,
     ) -> Self {
         let search_result_highlighter = matched_word
             .map(|matched_word| SearchResultHighlighter::new(matched_word, selected_match));
         Self {
-            syntax_highlighter: create_syntax_highlighter(file_type),
+            current_line_highlighter: None,
+            syntax: Some(syntax),
+            whitespace_highlighter: None,
+            ruler_highlighter: None,
+            occurrence_highlighter: occurrences_of.map(WordHighlighter::new),
             search_result_highlighter,
+            bracket_highlighter: brackets.map(|brackets| {
+                GraphemeHighlighter::new(brackets.to_vec(), AnnotationType::MatchingBracket)
+            }),
+            caret_highlighter: (!secondary_carets.is_empty()).then(|| {
+                GraphemeHighlighter::new(secondary_carets.to_vec(), AnnotationType::SecondaryCaret)
+            }),
+            selection_highlighter: (!selection.is_empty())
+                .then(|| SelectionHighlighter::new(selection)),
         }
     }
 
+    // Highlights the line the caret is on. Since the annotation is drawn
+    // beneath all others, it comes first.
+    pub fn highlight_current_line(&mut self, line_idx: LineIdx) {
+        self.current_line_highlighter = Some(CurrentLineHighlighter::new(line_idx));
+    }
+
+    // Returns the background annotation of the line, which continues behind its
+    // end up to the edge of the view.
+    pub fn line_background(&self, idx: LineIdx) -> Option<AnnotationType> {
+        self.current_line_highlighter
+            .as_ref()?
+            .get_annotations(idx)?
+            .first()
+            .map(|annotation| annotation.annotation_type)
+    }
+
+    // Highlights trailing whitespace, as well as all other whitespace if
+    // `show_all` is set. The caret is needed to leave out the whitespace being
+    // typed at the end of its line.
+    pub fn highlight_whitespace(&mut self, show_all: bool, caret: Location) {
+        self.whitespace_highlighter = Some(WhitespaceHighlighter::new(show_all, caret));
+    }
+
+    // Highlights the graphemes at the given columns.
+    pub fn highlight_rulers(&mut self, columns: Vec<ColIdx>, tab_width: ColIdx) {
+        self.ruler_highlighter =
+            (!columns.is_empty()).then(|| RulerHighlighter::new(columns, tab_width));
+    }
+
     pub fn get_annotations(&self, idx: LineIdx) -> Vec<Annotation> {
         let mut result = Vec::new();
 
-        if let Some(syntax_highlighter) = &self.syntax_highlighter {
-            if let Some(annotations) = syntax_highlighter.get_annotations(idx) {
+        if let Some(current_line_highlighter) = &self.current_line_highlighter {
+            if let Some(annotations) = current_line_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        // The syntax is classified by the buffer, ahead of drawing
+        if let Some(syntax) = self.syntax {
+            if let Some(annotations) = syntax.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        if let Some(whitespace_highlighter) = &self.whitespace_highlighter {
+            if let Some(annotations) = whitespace_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        if let Some(ruler_highlighter) = &self.ruler_highlighter {
+            if let Some(annotations) = ruler_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        if let Some(occurrence_highlighter) = &self.occurrence_highlighter {
+            if let Some(annotations) = occurrence_highlighter.get_annotations(idx) {
                 result.extend(annotations.iter().copied());
             }
         }
@@ -49,15 +137,48 @@ impl<'a> Highlighter<'a> {
                 result.extend(annotations.iter().copied());
             }
         }
+        if let Some(bracket_highlighter) = &self.bracket_highlighter {
+            if let Some(annotations) = bracket_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        if let Some(selection_highlighter) = &self.selection_highlighter {
+            if let Some(annotations) = selection_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
+        if let Some(caret_highlighter) = &self.caret_highlighter {
+            if let Some(annotations) = caret_highlighter.get_annotations(idx) {
+                result.extend(annotations.iter().copied());
+            }
+        }
         result
     }
 
     pub fn highlight(&mut self, idx: LineIdx, line: &Line) {
-        if let Some(syntax_highlighter) = &mut self.syntax_highlighter {
-            syntax_highlighter.highlight(idx, line);
+        if let Some(current_line_highlighter) = &mut self.current_line_highlighter {
+            current_line_highlighter.highlight(idx, line);
+        }
+        if let Some(whitespace_highlighter) = &mut self.whitespace_highlighter {
+            whitespace_highlighter.highlight(idx, line);
+        }
+        if let Some(ruler_highlighter) = &mut self.ruler_highlighter {
+            ruler_highlighter.highlight(idx, line);
+        }
+        if let Some(occurrence_highlighter) = &mut self.occurrence_highlighter {
+            occurrence_highlighter.highlight(idx, line);
         }
         if let Some(search_result_highlighter) = &mut self.search_result_highlighter {
             search_result_highlighter.highlight(idx, line);
         }
+        if let Some(bracket_highlighter) = &mut self.bracket_highlighter {
+            bracket_highlighter.highlight(idx, line);
+        }
+        if let Some(selection_highlighter) = &mut self.selection_highlighter {
+            selection_highlighter.highlight(idx, line);
+        }
+        if let Some(caret_highlighter) = &mut self.caret_highlighter {
+            caret_highlighter.highlight(idx, line);
+        }
     }
 }
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights the graphemes drawn at the ruler columns.
+pub struct RulerHighlighter {
+    columns: Vec<ColIdx>,
+    tab_width: ColIdx,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl RulerHighlighter {
+    pub fn new(columns: Vec<ColIdx>, tab_width: ColIdx) -> Self {
+        Self {
+            columns,
+            tab_width,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for RulerHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        let mut result = Vec::new();
+        for &col in &self.columns {
+            let grapheme_idx = line.grapheme_idx_at(col, self.tab_width);
+            if grapheme_idx < line.grapheme_count() {
+                result.push(Annotation {
+                    annotation_type: AnnotationType::Ruler,
+                    start: line.grapheme_idx_to_byte_idx(grapheme_idx),
+                    end: line.grapheme_idx_to_byte_idx(grapheme_idx.saturating_add(1)),
+                });
+            }
+        }
+        self.highlights.insert(idx, result);
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
@@ -1,9 +1,12 @@
+use std::{iter::Peekable, ops::Range, str::CharIndices};
+
 use unicode_segmentation::UnicodeSegmentation;
 
-use super::{Annotation, AnnotationType, Line, SyntaxHighlighter};
+use super::{Annotation, AnnotationType, Line, SequentialHighlighter, SyntaxHighlighter};
 use crate::prelude::*;
 
-const KEYWORDS: [&str; 52] = [
+const KEYWORDS: [&str; 53] = [
+    "as",
     "break",
     "const",
     "continue",
@@ -66,8 +69,20 @@ const KNOWN_VALUES: [&str; 6] = ["Some", "None", "true", "false", "Ok", "Err"];
 #[derive(Default)]
 pub struct RustSyntaxHighlighter {
     highlights: Vec<Vec<Annotation>>,
+    state: LineState,
+    // The state each highlighted line started in.
+    line_states: Vec<LineState>,
+}
+
+// What a line continues from the lines before it.
+#[derive(Default, Clone, Copy)]
+struct LineState {
     ml_comment_balance: usize,
+    // Whether the outermost open ML comment is a doc comment.
+    in_ml_doc_comment: bool,
     in_ml_string: bool,
+    // The number of `#` closing the raw string the previous line ended in.
+    raw_string_hashes: Option<usize>,
 }
 
 impl RustSyntaxHighlighter {
@@ -77,18 +92,23 @@ impl RustSyntaxHighlighter {
         while let Some((_, char)) = chars.next() {
             if char == '/' {
                 // check for an ml comment opener
-                if let Some((_, '*')) = chars.peek() {
-                    self.ml_comment_balance = self.ml_comment_balance.saturating_add(1);
+                if let Some(&(star_idx, '*')) = chars.peek() {
+                    if self.state.ml_comment_balance == 0 {
+                        self.state.in_ml_doc_comment = string
+                            .get(star_idx.saturating_add(1)..)
+                            .is_some_and(is_block_doc_comment_body);
+                    }
+                    self.state.ml_comment_balance = self.state.ml_comment_balance.saturating_add(1);
                     chars.next();
                 }
-            } else if self.ml_comment_balance == 0 {
+            } else if self.state.ml_comment_balance == 0 {
                 return None; // We saw no opener, and we are not currently in a ML comment, returning None
             } else if char == '*' {
                 if let Some((idx, '/')) = chars.peek() {
-                    self.ml_comment_balance = self.ml_comment_balance.saturating_sub(1);
-                    if self.ml_comment_balance == 0 {
+                    self.state.ml_comment_balance = self.state.ml_comment_balance.saturating_sub(1);
+                    if self.state.ml_comment_balance == 0 {
                         return Some(Annotation {
-                            annotation_type: AnnotationType::Comment,
+                            annotation_type: self.ml_comment_type(),
                             start: 0,
                             end: idx.saturating_add(1),
                         });
@@ -97,46 +117,98 @@ impl RustSyntaxHighlighter {
                 }
             }
         }
-        (self.ml_comment_balance > 0).then_some(Annotation {
-            annotation_type: AnnotationType::Comment,
+        (self.state.ml_comment_balance > 0).then_some(Annotation {
+            annotation_type: self.ml_comment_type(),
             start: 0,
             end: string.len(),
         })
     }
 
+    const fn ml_comment_type(&self) -> AnnotationType {
+        if self.state.in_ml_doc_comment {
+            AnnotationType::DocComment
+        } else {
+            AnnotationType::Comment
+        }
+    }
+
     fn annotate_string(&mut self, string: &str) -> Option<Annotation> {
         let mut chars = string.char_indices();
         while let Some((idx, char)) = chars.next() {
-            if char == '\\' && self.in_ml_string {
+            if char == '\\' && self.state.in_ml_string {
                 chars.next(); // Skip the escape character.
                 continue;
             }
             if char == '"' {
-                if self.in_ml_string {
-                    self.in_ml_string = false;
+                if self.state.in_ml_string {
+                    self.state.in_ml_string = false;
                     return Some(Annotation {
                         annotation_type: AnnotationType::String,
                         start: 0,
                         end: idx.saturating_add(1),
                     });
                 }
-                self.in_ml_string = true;
+                self.state.in_ml_string = true;
             }
-            if !self.in_ml_string {
+            if !self.state.in_ml_string {
                 return None;
             }
         }
-        self.in_ml_string.then_some(Annotation {
+        self.state.in_ml_string.then_some(Annotation {
             annotation_type: AnnotationType::String,
             start: 0,
             end: string.len(),
         })
     }
 
+    // Annotates a raw string such as `r#"…"#`, optionally prefixed with `b` or
+    // `c`.
+    fn annotate_raw_string(&mut self, string: &str) -> Option<Annotation> {
+        let rest = string
+            .strip_prefix("br")
+            .or_else(|| string.strip_prefix("cr"))
+            .or_else(|| string.strip_prefix('r'))?;
+        let after_hashes = rest.trim_start_matches('#');
+        let hashes = rest.len().saturating_sub(after_hashes.len());
+        let body = after_hashes.strip_prefix('"')?;
+        Some(self.close_raw_string(string, string.len().saturating_sub(body.len()), hashes))
+    }
+
+    // Annotates a raw string up to and including its closing quote and hashes,
+    // which are searched for from the given index on.
+    fn close_raw_string(&mut self, string: &str, from: ByteIdx, hashes: usize) -> Annotation {
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let closing_idx = string
+            .get(from..)
+            .and_then(|rest| rest.find(&closing))
+            .map(|idx| idx.saturating_add(from));
+        self.state.raw_string_hashes = closing_idx.is_none().then_some(hashes);
+        Annotation {
+            annotation_type: AnnotationType::String,
+            start: 0,
+            end: closing_idx.map_or(string.len(), |idx| idx.saturating_add(closing.len())),
+        }
+    }
+
+    // Annotates a byte string `b"…"` or a C string `c"…"`.
+    fn annotate_prefixed_string(&mut self, string: &str) -> Option<Annotation> {
+        let rest = string
+            .strip_prefix('b')
+            .or_else(|| string.strip_prefix('c'))?;
+        if !rest.starts_with('"') {
+            return None;
+        }
+        let mut annotation = self.annotate_string(rest)?;
+        annotation.end = annotation.end.saturating_add(1);
+        Some(annotation)
+    }
+
     fn initial_annotation(&mut self, line: &Line) -> Option<Annotation> {
-        if self.in_ml_string {
+        if let Some(hashes) = self.state.raw_string_hashes {
+            Some(self.close_raw_string(line, 0, hashes))
+        } else if self.state.in_ml_string {
             self.annotate_string(line)
-        } else if self.ml_comment_balance > 0 {
+        } else if self.state.ml_comment_balance > 0 {
             self.annotate_ml_comment(line)
         } else {
             None
@@ -146,11 +218,16 @@ impl RustSyntaxHighlighter {
     fn annotate_remainder(&mut self, remainder: &str) -> Option<Annotation> {
         self.annotate_ml_comment(remainder)
             .or_else(|| self.annotate_string(remainder))
+            .or_else(|| self.annotate_raw_string(remainder))
+            .or_else(|| self.annotate_prefixed_string(remainder))
             .or_else(|| annotate_single_line_comment(remainder))
+            .or_else(|| annotate_attribute(remainder))
+            .or_else(|| annotate_byte_char(remainder))
             .or_else(|| annotate_char(remainder))
             .or_else(|| annotate_lifetime_specifier(remainder))
             .or_else(|| annotate_number(remainder))
             .or_else(|| annotate_keyword(remainder))
+            .or_else(|| annotate_macro(remainder))
             .or_else(|| annotate_type(remainder))
             .or_else(|| annotate_known_value(remainder))
     }
@@ -159,10 +236,15 @@ impl RustSyntaxHighlighter {
 impl SyntaxHighlighter for RustSyntaxHighlighter {
     fn highlight(&mut self, idx: LineIdx, line: &Line) {
         debug_assert_eq!(idx, self.highlights.len());
+        self.line_states.push(self.state);
         let mut result = Vec::new();
         let mut iterator = line.split_word_bound_indices().peekable();
+        let continues_raw_string = self.state.raw_string_hashes.is_some();
         if let Some(annotation) = self.initial_annotation(line) {
             result.push(annotation);
+            if annotation.annotation_type == AnnotationType::String && !continues_raw_string {
+                result.extend(annotate_escapes(line, annotation.start..annotation.end));
+            }
 
             while let Some(&(next_idx, _)) = iterator.peek() {
                 if next_idx >= annotation.end {
@@ -176,6 +258,9 @@ impl SyntaxHighlighter for RustSyntaxHighlighter {
             if let Some(mut annotation) = self.annotate_remainder(remainder) {
                 annotation.shift(start_idx);
                 result.push(annotation);
+                if has_escapes(line, annotation) {
+                    result.extend(annotate_escapes(line, annotation.start..annotation.end));
+                }
 
                 while let Some(&(next_idx, _)) = iterator.peek() {
                     if next_idx >= annotation.end {
@@ -193,6 +278,16 @@ impl SyntaxHighlighter for RustSyntaxHighlighter {
     }
 }
 
+impl SequentialHighlighter for RustSyntaxHighlighter {
+    fn truncate(&mut self, idx: LineIdx) {
+        if let Some(&state) = self.line_states.get(idx) {
+            self.state = state;
+            self.line_states.truncate(idx);
+            self.highlights.truncate(idx);
+        }
+    }
+}
+
 fn annotate_lifetime_specifier(string: &str) -> Option<Annotation> {
     let mut iter = string.split_word_bound_indices();
     if let Some((_, "\'")) = iter.next() {
@@ -209,8 +304,15 @@ fn annotate_lifetime_specifier(string: &str) -> Option<Annotation> {
 
 fn annotate_single_line_comment(string: &str) -> Option<Annotation> {
     if string.starts_with("//") {
+        // `///` and `//!` start doc comments, but `////` is a regular one again
+        let is_doc_comment =
+            (string.starts_with("///") && !string.starts_with("////")) || string.starts_with("//!");
         return Some(Annotation {
-            annotation_type: AnnotationType::Comment,
+            annotation_type: if is_doc_comment {
+                AnnotationType::DocComment
+            } else {
+                AnnotationType::Comment
+            },
             start: 0,
             end: string.len(),
         });
@@ -218,6 +320,72 @@ fn annotate_single_line_comment(string: &str) -> Option<Annotation> {
     None
 }
 
+// Whether the text following a `/*` makes it a doc comment: `/**` and `/*!`
+// do, but `/***` and the empty `/**/` do not.
+fn is_block_doc_comment_body(body: &str) -> bool {
+    body.starts_with('!')
+        || (body.starts_with('*') && !body.starts_with("**") && !body.starts_with("*/"))
+}
+
+// Annotates an outer `#[…]` or inner `#![…]` attribute, up to its closing
+// bracket or the end of the line.
+fn annotate_attribute(string: &str) -> Option<Annotation> {
+    let body = string
+        .strip_prefix("#[")
+        .or_else(|| string.strip_prefix("#!["))?;
+    let mut depth: usize = 1;
+    let mut in_string = false;
+    let mut chars = body.char_indices();
+    while let Some((idx, char)) = chars.next() {
+        match char {
+            '\\' if in_string => {
+                chars.next(); // Skip the escaped character.
+            }
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth = depth.saturating_add(1),
+            ']' if !in_string => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(Annotation {
+                        annotation_type: AnnotationType::Attribute,
+                        start: 0,
+                        end: string
+                            .len()
+                            .saturating_sub(body.len())
+                            .saturating_add(idx)
+                            .saturating_add(1),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(Annotation {
+        annotation_type: AnnotationType::Attribute,
+        start: 0,
+        end: string.len(),
+    })
+}
+
+// Annotates a macro invocation such as `println!`, but not a comparison such
+// as `a != b`.
+fn annotate_macro(string: &str) -> Option<Annotation> {
+    let word = string.split_word_bounds().next()?;
+    if !word
+        .chars()
+        .next()
+        .is_some_and(|char| char.is_alphabetic() || char == '_')
+    {
+        return None;
+    }
+    let rest = string.get(word.len()..)?;
+    (rest.starts_with('!') && !rest.starts_with("!=")).then_some(Annotation {
+        annotation_type: AnnotationType::Macro,
+        start: 0,
+        end: word.len().saturating_add(1),
+    })
+}
+
 fn is_valid_number(word: &str) -> bool {
     if word.is_empty() {
         return false;
@@ -339,13 +507,92 @@ fn annotate_known_value(string: &str) -> Option<Annotation> {
     annotate_next_word(string, AnnotationType::KnownValue, is_known_value)
 }
 
+// Annotates a byte literal such as `b'a'`.
+fn annotate_byte_char(string: &str) -> Option<Annotation> {
+    let mut annotation = annotate_char(string.strip_prefix('b')?)?;
+    annotation.end = annotation.end.saturating_add(1);
+    Some(annotation)
+}
+
+// Whether the annotation is a string or character literal in which escape
+// sequences apply, that is one which is not raw.
+fn has_escapes(line: &str, annotation: Annotation) -> bool {
+    match annotation.annotation_type {
+        AnnotationType::Char => true,
+        AnnotationType::String => line
+            .get(annotation.start..annotation.end)
+            .is_some_and(|literal| !literal.trim_start_matches(['b', 'c']).starts_with('r')),
+        _ => false,
+    }
+}
+
+// Annotates the escape sequences, such as `\n`, `\x7f` or `\u{1F600}`, within
+// the given range of a literal. A trailing backslash continues a string on
+// the next line.
+fn annotate_escapes(line: &str, range: Range<ByteIdx>) -> Vec<Annotation> {
+    let mut result = Vec::new();
+    let Some(literal) = line.get(range.clone()) else {
+        return result;
+    };
+    let mut chars = literal.char_indices().peekable();
+    while let Some((idx, char)) = chars.next() {
+        if char != '\\' {
+            continue;
+        }
+        let end = match chars.next() {
+            Some((_, 'x')) => skip_while(&mut chars, literal, 2, |char| char.is_ascii_hexdigit()),
+            Some((_, 'u')) if matches!(chars.peek(), Some((_, '{'))) => {
+                let end = skip_while(&mut chars, literal, usize::MAX, |char| char != '}');
+                chars.next().map_or(end, |(idx, _)| idx.saturating_add(1))
+            }
+            Some((next_idx, next)) => next_idx.saturating_add(next.len_utf8()),
+            None => literal.len(),
+        };
+        result.push(Annotation {
+            annotation_type: AnnotationType::Escape,
+            start: range.start.saturating_add(idx),
+            end: range.start.saturating_add(end),
+        });
+    }
+    result
+}
+
+// Consumes up to `limit` characters matching the predicate, and returns the
+// index following the last consumed one.
+fn skip_while<F>(
+    chars: &mut Peekable<CharIndices>,
+    string: &str,
+    limit: usize,
+    predicate: F,
+) -> ByteIdx
+where
+    F: Fn(char) -> bool,
+{
+    let mut end = chars.peek().map_or(string.len(), |&(idx, _)| idx);
+    for _ in 0..limit {
+        match chars.peek() {
+            Some(&(idx, char)) if predicate(char) => {
+                end = idx.saturating_add(char.len_utf8());
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+    end
+}
+
 fn annotate_char(string: &str) -> Option<Annotation> {
     let mut iter = string.split_word_bound_indices().peekable();
     if let Some((_, "\'")) = iter.next() {
-        if let Some((_, "\\")) = iter.peek() {
+        let escaped = matches!(iter.peek(), Some((_, "\\")));
+        if escaped {
             iter.next();
         }
-        iter.next();
+        let character = iter.next();
+        // A unicode escape spans several words, up to its closing brace
+        if escaped && matches!(character, Some((_, "u"))) && matches!(iter.peek(), Some((_, "{"))) {
+            iter.find(|&(_, word)| word == "}");
+        }
         if let Some((idx, "\'")) = iter.next() {
             return Some(Annotation {
                 annotation_type: AnnotationType::Char,
@@ -0,0 +1,55 @@
+use std::{collections::HashMap, ops::Range};
+
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights the selected ranges. A block selection consists of one range per
+// line, any other selection of a single range.
+pub struct SelectionHighlighter {
+    ranges: Vec<Range<Location>>,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl SelectionHighlighter {
+    pub fn new(ranges: Vec<Range<Location>>) -> Self {
+        Self {
+            ranges,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for SelectionHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        let mut result = Vec::new();
+        for Range { start, end } in &self.ranges {
+            if idx < start.line_idx || idx > end.line_idx {
+                continue;
+            }
+            let start_byte_idx = if idx == start.line_idx {
+                line.grapheme_idx_to_byte_idx(start.grapheme_idx)
+            } else {
+                0
+            };
+            let end_byte_idx = if idx == end.line_idx {
+                line.grapheme_idx_to_byte_idx(end.grapheme_idx)
+            } else {
+                line.len()
+            };
+            if start_byte_idx < end_byte_idx {
+                result.push(Annotation {
+                    annotation_type: AnnotationType::Selection,
+                    start: start_byte_idx,
+                    end: end_byte_idx,
+                });
+            }
+        }
+        if !result.is_empty() {
+            self.highlights.insert(idx, result);
+        }
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::{max, min};
+
+#[cfg(feature = "tree-sitter")]
+use super::SyntaxTree;
+use super::{
+    Annotation, AnnotationType, FileType, LanguageSyntaxHighlighter, Line, RustSyntaxHighlighter,
+    SequentialHighlighter,
+};
+use crate::prelude::*;
+
+// A position in the text of a buffer.
+#[derive(Default, Clone, Copy, Eq, PartialEq, Debug)]
+pub struct TextPosition {
+    pub line_idx: LineIdx,
+    pub byte_idx: ByteIdx,
+    // The offset from the start of the buffer, with lines separated by `\n`.
+    #[cfg(feature = "tree-sitter")]
+    pub offset: ByteIdx,
+}
+
+impl TextPosition {
+    // Returns the position behind the given text inserted at this position.
+    pub fn after(self, text: &str) -> Self {
+        let (line_idx, byte_idx) = match text.rfind('\n') {
+            Some(newline_idx) => (
+                self.line_idx.saturating_add(text.matches('\n').count()),
+                text.len().saturating_sub(newline_idx).saturating_sub(1),
+            ),
+            None => (self.line_idx, self.byte_idx.saturating_add(text.len())),
+        };
+        Self {
+            line_idx,
+            byte_idx,
+            #[cfg(feature = "tree-sitter")]
+            offset: self.offset.saturating_add(text.len()),
+        }
+    }
+}
+
+// The replacement of the text between `start` and `old_end` with the text
+// between `start` and `new_end`. Line based highlighters only need the start.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+pub struct TextEdit {
+    pub start: TextPosition,
+    pub old_end: TextPosition,
+    pub new_end: TextPosition,
+}
+
+// The syntax annotations of a buffer. They are kept until the buffer is edited,
+// after which only the lines from the first edited one on are classified
+// again, and only once they are needed.
+#[derive(Default)]
+pub struct Syntax {
+    highlighter: Option<Box<dyn SequentialHighlighter>>,
+    // The number of lines at the start of the buffer which are classified.
+    highlighted: LineIdx,
+    #[cfg(feature = "tree-sitter")]
+    syntax_tree: Option<SyntaxTree>,
+}
+
+impl Syntax {
+    pub fn new(file_type: FileType) -> Self {
+        #[cfg(feature = "tree-sitter")]
+        if let Some(syntax_tree) = SyntaxTree::new(file_type) {
+            return Self {
+                syntax_tree: Some(syntax_tree),
+                ..Self::default()
+            };
+        }
+        let highlighter: Option<Box<dyn SequentialHighlighter>> = match file_type {
+            FileType::Rust => Some(Box::<RustSyntaxHighlighter>::default()),
+            FileType::Language(language) => {
+                Some(Box::new(LanguageSyntaxHighlighter::new(language)))
+            }
+            FileType::Text => None,
+        };
+        Self {
+            highlighter,
+            ..Self::default()
+        }
+    }
+
+    // Invalidates the annotations touched by the edit.
+    pub fn edit(&mut self, edit: &TextEdit) {
+        let line_idx = edit.start.line_idx;
+        if let Some(highlighter) = &mut self.highlighter {
+            highlighter.truncate(line_idx);
+        }
+        self.highlighted = min(self.highlighted, line_idx);
+        #[cfg(feature = "tree-sitter")]
+        if let Some(syntax_tree) = &mut self.syntax_tree {
+            syntax_tree.edit(edit);
+        }
+    }
+
+    // Classifies the lines up to and including the given one, unless they
+    // already are.
+    pub fn highlight_until(&mut self, lines: &[Line], idx: LineIdx) {
+        #[cfg(feature = "tree-sitter")]
+        if let Some(syntax_tree) = &mut self.syntax_tree {
+            syntax_tree.update(lines);
+            return;
+        }
+        let Some(highlighter) = &mut self.highlighter else {
+            return;
+        };
+        let end = min(idx.saturating_add(1), lines.len());
+        for (line_idx, line) in lines.iter().enumerate().take(end).skip(self.highlighted) {
+            highlighter.highlight(line_idx, line);
+        }
+        self.highlighted = max(self.highlighted, end);
+    }
+
+    // Returns the annotations of a classified line.
+    pub fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        #[cfg(feature = "tree-sitter")]
+        if let Some(syntax_tree) = &self.syntax_tree {
+            return syntax_tree.get_annotations(idx);
+        }
+        self.highlighter.as_ref()?.get_annotations(idx)
+    }
+
+    // Whether the given byte of a classified line lies within a string, a
+    // character literal or a comment.
+    pub fn is_literal_or_comment(&self, idx: LineIdx, byte_idx: ByteIdx) -> bool {
+        self.literal_or_comment_at(idx, byte_idx).is_some()
+    }
+
+    // Returns the type of the string, character literal or comment annotation
+    // containing the given byte of a classified line, if any.
+    pub fn literal_or_comment_at(&self, idx: LineIdx, byte_idx: ByteIdx) -> Option<AnnotationType> {
+        self.get_annotations(idx)?
+            .iter()
+            .find(|annotation| {
+                matches!(
+                    annotation.annotation_type,
+                    AnnotationType::String
+                        | AnnotationType::Char
+                        | AnnotationType::Comment
+                        | AnnotationType::DocComment
+                ) && annotation.start <= byte_idx
+                    && byte_idx < annotation.end
+            })
+            .map(|annotation| annotation.annotation_type)
+    }
+}
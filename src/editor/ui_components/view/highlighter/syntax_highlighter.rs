@@ -5,3 +5,12 @@ pub trait SyntaxHighlighter {
     fn highlight(&mut self, idx: LineIdx, line: &Line);
     fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>>;
 }
+
+// A highlighter which carries state from one line to the next, such as an open
+// block comment, and therefore highlights the lines in order.
+pub trait SequentialHighlighter: SyntaxHighlighter {
+    // Forgets the given line and all lines behind it, so that they can be
+    // highlighted again after an edit, starting in the state the given line
+    // started in.
+    fn truncate(&mut self, idx: LineIdx);
+}
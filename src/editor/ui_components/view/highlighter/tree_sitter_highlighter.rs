@@ -0,0 +1,397 @@
+use std::{
+    cmp::{max, min},
+    iter::once,
+    ops::Range,
+};
+
+use tree_sitter::{InputEdit, Node, Parser, Point, Query, QueryCursor, StreamingIterator, Tree};
+
+use super::{Annotation, AnnotationType, FileType, Line, TextEdit, TextPosition};
+use crate::prelude::*;
+
+// The syntax tree of a buffer whose file type has a tree-sitter grammar. Edits
+// of the buffer are applied to the tree as they happen, and the tree is
+// reparsed incrementally the next time its annotations are needed. Only the
+// annotations of edited lines and of lines whose syntax changed are collected
+// again.
+pub struct SyntaxTree {
+    parser: Parser,
+    query: Query,
+    tree: Option<Tree>,
+    // The annotations of each line, as of the last parse.
+    highlights: Vec<Vec<Annotation>>,
+    // The lines edited since the last parse, if any.
+    edited_lines: Option<Range<LineIdx>>,
+}
+
+impl SyntaxTree {
+    pub fn new(file_type: FileType) -> Option<Self> {
+        let (language, highlights_query) = file_type.tree_sitter_grammar()?;
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        let query = Query::new(&language, highlights_query).ok()?;
+        Some(Self {
+            parser,
+            query,
+            tree: None,
+            highlights: Vec::new(),
+            edited_lines: None,
+        })
+    }
+
+    pub fn edit(&mut self, edit: &TextEdit) {
+        if let Some(tree) = &mut self.tree {
+            tree.edit(&input_edit(edit));
+        }
+        let (start, old_end, new_end) = (
+            edit.start.line_idx,
+            edit.old_end.line_idx,
+            edit.new_end.line_idx,
+        );
+        // The annotations of the edited lines are left empty until the next parse
+        if start <= self.highlights.len() {
+            let old_lines = start..min(old_end.saturating_add(1), self.highlights.len());
+            let new_count = new_end.saturating_sub(start).saturating_add(1);
+            self.highlights
+                .splice(old_lines, vec![Vec::new(); new_count]);
+        }
+        let new_lines = start..new_end.saturating_add(1);
+        self.edited_lines = Some(match self.edited_lines.take() {
+            Some(lines) => {
+                let first = line_after_edit(lines.start, edit);
+                let last = line_after_edit(lines.end.saturating_sub(1), edit);
+                min(first, new_lines.start)..max(last.saturating_add(1), new_lines.end)
+            }
+            None => new_lines,
+        });
+    }
+
+    // Reparses the lines and collects their annotations, if they changed since
+    // the last parse.
+    pub fn update(&mut self, lines: &[Line]) {
+        if self.tree.is_some() && self.edited_lines.is_none() {
+            return;
+        }
+        let old_tree = self.tree.take();
+        let mut read = |_, point: Point| read_chunk(lines, point);
+        self.tree = self
+            .parser
+            .parse_with_options(&mut read, old_tree.as_ref(), None);
+        self.highlights.resize(lines.len(), Vec::new());
+        let changed_rows = match (&old_tree, &self.tree, self.edited_lines.take()) {
+            (Some(old_tree), Some(tree), Some(edited_lines)) => {
+                Some(changed_rows(old_tree, tree, edited_lines))
+            }
+            _ => None,
+        };
+        match changed_rows {
+            Some(changed_rows) => {
+                for rows in changed_rows {
+                    self.highlight_rows(lines, rows);
+                }
+            }
+            None => self.highlight_rows(lines, 0..lines.len()),
+        }
+    }
+
+    pub fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(idx)
+    }
+
+    // Collects the annotations of the given lines again, split at line breaks.
+    fn highlight_rows(&mut self, lines: &[Line], rows: Range<LineIdx>) {
+        let rows = rows.start..min(rows.end, lines.len());
+        for line_highlights in self.highlights.get_mut(rows.clone()).unwrap_or_default() {
+            line_highlights.clear();
+        }
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        let mut cursor = QueryCursor::new();
+        cursor.set_point_range(Point::new(rows.start, 0)..Point::new(rows.end, 0));
+        let node_text = |node: Node| node_text(lines, node);
+        let mut captures = cursor.captures(&self.query, tree.root_node(), node_text);
+        let mut previous_range: Option<Range<ByteIdx>> = None;
+        while let Some((query_match, capture_idx)) = captures.next() {
+            let Some(capture) = query_match.captures.get(*capture_idx) else {
+                continue;
+            };
+            let node = capture.node;
+            // Like in tree-sitter's own highlighter, the first pattern
+            // capturing a node wins.
+            if previous_range.as_ref() == Some(&node.byte_range()) {
+                continue;
+            }
+            previous_range = Some(node.byte_range());
+            let annotation_type = usize::try_from(capture.index)
+                .ok()
+                .and_then(|idx| self.query.capture_names().get(idx))
+                .and_then(|name| annotation_type(name, node.kind()));
+            let Some(annotation_type) = annotation_type else {
+                continue;
+            };
+            let (start, end) = (node.start_position(), node.end_position());
+            let last_row = min(end.row, rows.end.saturating_sub(1));
+            for row in max(start.row, rows.start)..=last_row {
+                let (Some(line_highlights), Some(line)) =
+                    (self.highlights.get_mut(row), lines.get(row))
+                else {
+                    break;
+                };
+                let start = if row == start.row { start.column } else { 0 };
+                let end = if row == end.row {
+                    end.column
+                } else {
+                    line.len()
+                };
+                if start < end {
+                    line_highlights.push(Annotation {
+                        annotation_type,
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// Returns the line at which the given line ends up after the edit. Lines within
+// the edited text end up on its last line.
+const fn line_after_edit(idx: LineIdx, edit: &TextEdit) -> LineIdx {
+    if idx <= edit.start.line_idx {
+        idx
+    } else if idx > edit.old_end.line_idx {
+        idx.saturating_sub(edit.old_end.line_idx)
+            .saturating_add(edit.new_end.line_idx)
+    } else {
+        edit.new_end.line_idx
+    }
+}
+
+// Returns the edited lines together with the lines whose syntax changed in the
+// new tree, as ordered and disjoint ranges.
+fn changed_rows(old_tree: &Tree, tree: &Tree, edited_lines: Range<LineIdx>) -> Vec<Range<LineIdx>> {
+    let mut rows: Vec<Range<LineIdx>> = old_tree
+        .changed_ranges(tree)
+        .map(|range| range.start_point.row..range.end_point.row.saturating_add(1))
+        .chain(once(edited_lines))
+        .collect();
+    rows.sort_by_key(|rows| rows.start);
+    let mut merged: Vec<Range<LineIdx>> = Vec::new();
+    for rows in rows {
+        match merged.last_mut() {
+            Some(last) if rows.start <= last.end => last.end = max(last.end, rows.end),
+            _ => merged.push(rows),
+        }
+    }
+    merged
+}
+
+fn input_edit(edit: &TextEdit) -> InputEdit {
+    InputEdit {
+        start_byte: edit.start.offset,
+        old_end_byte: edit.old_end.offset,
+        new_end_byte: edit.new_end.offset,
+        start_position: point(edit.start),
+        old_end_position: point(edit.old_end),
+        new_end_position: point(edit.new_end),
+    }
+}
+
+const fn point(position: TextPosition) -> Point {
+    Point::new(position.line_idx, position.byte_idx)
+}
+
+// Returns the text from the given point to the end of its line, including the
+// line break, as the parser reads the lines joined by `\n`.
+fn read_chunk(lines: &[Line], point: Point) -> &[u8] {
+    let Some(line) = lines.get(point.row) else {
+        return &[];
+    };
+    match line.as_bytes().get(point.column..) {
+        Some(rest) if !rest.is_empty() => rest,
+        _ if point.row.saturating_add(1) < lines.len() => b"\n",
+        _ => &[],
+    }
+}
+
+// Returns the text of the node, line by line, for the predicates of the query.
+fn node_text<'a>(lines: &'a [Line], node: Node) -> impl Iterator<Item = &'a [u8]> {
+    let (start, end) = (node.start_position(), node.end_position());
+    lines
+        .iter()
+        .enumerate()
+        .take(end.row.saturating_add(1))
+        .skip(start.row)
+        .flat_map(move |(row, line)| {
+            let from = if row == start.row { start.column } else { 0 };
+            let to = if row == end.row {
+                end.column
+            } else {
+                line.len()
+            };
+            let text = line.as_bytes().get(from..to).unwrap_or_default();
+            let separator: &[u8] = if row == start.row { b"" } else { b"\n" };
+            once(separator).chain(once(text))
+        })
+}
+
+// Maps a capture of the highlights query to the annotation drawn for it.
+// Literals share capture names with other constants, so they are told apart
+// by the kind of node.
+fn annotation_type(capture_name: &str, node_kind: &str) -> Option<AnnotationType> {
+    match node_kind {
+        "integer_literal" | "float_literal" => return Some(AnnotationType::Number),
+        "char_literal" => return Some(AnnotationType::Char),
+        _ => {}
+    }
+    match capture_name {
+        "comment" => Some(AnnotationType::Comment),
+        "comment.documentation" => Some(AnnotationType::DocComment),
+        "string" => Some(AnnotationType::String),
+        "escape" => Some(AnnotationType::Escape),
+        "attribute" => Some(AnnotationType::Attribute),
+        "function.macro" => Some(AnnotationType::Macro),
+        "keyword" => Some(AnnotationType::KeyWord),
+        "type" | "type.builtin" | "constructor" => Some(AnnotationType::Type),
+        "constant" | "constant.builtin" => Some(AnnotationType::KnownValue),
+        "label" => Some(AnnotationType::LifetimeSpecifier),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_are_read_line_by_line() {
+        let lines = [Line::from("fn a"), Line::from("é")];
+        assert_eq!(read_chunk(&lines, Point::new(0, 0)), b"fn a");
+        assert_eq!(read_chunk(&lines, Point::new(0, 3)), b"a");
+        assert_eq!(read_chunk(&lines, Point::new(0, 4)), b"\n");
+        assert_eq!(read_chunk(&lines, Point::new(1, 0)), "é".as_bytes());
+        assert_eq!(read_chunk(&lines, Point::new(1, 2)), b"");
+        assert_eq!(read_chunk(&lines, Point::new(2, 0)), b"");
+    }
+
+    #[test]
+    fn edits_map_to_input_edits() {
+        let start = TextPosition {
+            line_idx: 1,
+            byte_idx: 2,
+            offset: 7,
+        };
+        let edit = TextEdit {
+            start,
+            old_end: start.after("é\nab"),
+            new_end: start.after("x"),
+        };
+        let input_edit = input_edit(&edit);
+        assert_eq!(input_edit.start_byte, 7);
+        assert_eq!(input_edit.old_end_byte, 12);
+        assert_eq!(input_edit.new_end_byte, 8);
+        assert_eq!(input_edit.start_position, Point::new(1, 2));
+        assert_eq!(input_edit.old_end_position, Point::new(2, 2));
+        assert_eq!(input_edit.new_end_position, Point::new(1, 3));
+    }
+
+    #[test]
+    fn edited_tree_matches_fresh_parse() {
+        let mut lines = vec![Line::from("fn a() {"), Line::from("}")];
+        let mut syntax_tree = SyntaxTree::new(FileType::Rust).unwrap();
+        syntax_tree.update(&lines);
+        // Turn the closing brace into a comment
+        let start = TextPosition {
+            line_idx: 1,
+            byte_idx: 0,
+            offset: 9,
+        };
+        syntax_tree.edit(&TextEdit {
+            start,
+            old_end: start,
+            new_end: start.after("// "),
+        });
+        lines[1] = Line::from("// }");
+        syntax_tree.update(&lines);
+        let mut fresh = SyntaxTree::new(FileType::Rust).unwrap();
+        fresh.update(&lines);
+        assert_eq!(syntax_tree.get_annotations(1), fresh.get_annotations(1));
+        assert_eq!(
+            syntax_tree
+                .get_annotations(1)
+                .map(|annotations| annotations[0].annotation_type),
+            Some(AnnotationType::Comment)
+        );
+    }
+
+    #[test]
+    fn edits_across_lines_match_fresh_parse() {
+        let mut lines: Vec<Line> = ["/* a */", "fn b() {}", "fn c() {}", "// */"]
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        let mut syntax_tree = SyntaxTree::new(FileType::Rust).unwrap();
+        syntax_tree.update(&lines);
+        // Removing the end of the comment extends it over the unedited lines
+        let start = TextPosition {
+            line_idx: 0,
+            byte_idx: 4,
+            offset: 4,
+        };
+        syntax_tree.edit(&TextEdit {
+            start,
+            old_end: start.after(" */"),
+            new_end: start,
+        });
+        lines[0] = Line::from("/* a");
+        syntax_tree.update(&lines);
+        let mut fresh = SyntaxTree::new(FileType::Rust).unwrap();
+        fresh.update(&lines);
+        assert_eq!(syntax_tree.highlights, fresh.highlights);
+        assert_eq!(
+            syntax_tree
+                .get_annotations(2)
+                .map(|annotations| annotations[0]),
+            Some(Annotation {
+                annotation_type: AnnotationType::Comment,
+                start: 0,
+                end: 9,
+            })
+        );
+        // Adding a line in front shifts the annotations of the lines behind it
+        let start = TextPosition::default();
+        syntax_tree.edit(&TextEdit {
+            start,
+            old_end: start,
+            new_end: start.after("fn d() {}\n"),
+        });
+        lines.insert(0, Line::from("fn d() {}"));
+        syntax_tree.update(&lines);
+        let mut fresh = SyntaxTree::new(FileType::Rust).unwrap();
+        fresh.update(&lines);
+        assert_eq!(syntax_tree.highlights, fresh.highlights);
+    }
+
+    #[test]
+    fn lines_behind_edits_shift() {
+        let edit = TextEdit {
+            start: TextPosition {
+                line_idx: 2,
+                ..TextPosition::default()
+            },
+            old_end: TextPosition {
+                line_idx: 4,
+                ..TextPosition::default()
+            },
+            new_end: TextPosition {
+                line_idx: 3,
+                ..TextPosition::default()
+            },
+        };
+        assert_eq!(line_after_edit(1, &edit), 1);
+        assert_eq!(line_after_edit(3, &edit), 3);
+        assert_eq!(line_after_edit(6, &edit), 5);
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights trailing whitespace and, if whitespace is shown, all other
+// spaces and tabs. Trailing whitespace right in front of the caret is left
+// alone, so that typing a space at the end of a line doesn't flash.
+pub struct WhitespaceHighlighter {
+    show_all: bool,
+    caret: Location,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl WhitespaceHighlighter {
+    pub fn new(show_all: bool, caret: Location) -> Self {
+        Self {
+            show_all,
+            caret,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for WhitespaceHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        let mut result = Vec::new();
+        let text = str::trim_end(line);
+        let trailing_start = text.len();
+        if self.show_all {
+            // The text in front of the trailing whitespace ends in a non-blank
+            // character, which closes every run of whitespace
+            let mut whitespace_start = None;
+            for (byte_idx, character) in text.char_indices() {
+                match (whitespace_start, character == ' ' || character == '\t') {
+                    (None, true) => whitespace_start = Some(byte_idx),
+                    (Some(start), false) => {
+                        result.push(Annotation {
+                            annotation_type: AnnotationType::Whitespace,
+                            start,
+                            end: byte_idx,
+                        });
+                        whitespace_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let caret_at_end =
+            self.caret.line_idx == idx && self.caret.grapheme_idx >= line.grapheme_count();
+        if trailing_start < line.len() && !caret_at_end {
+            result.push(Annotation {
+                annotation_type: AnnotationType::TrailingWhitespace,
+                start: trailing_start,
+                end: line.len(),
+            });
+        }
+        self.highlights.insert(idx, result);
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use super::{Annotation, AnnotationType, Line, syntax_highlighter::SyntaxHighlighter};
+use crate::prelude::*;
+
+// Highlights the whole-word occurrences of a word, such as the identifier
+// under the caret.
+pub struct WordHighlighter {
+    word: String,
+    highlights: HashMap<LineIdx, Vec<Annotation>>,
+}
+
+impl WordHighlighter {
+    pub fn new(word: String) -> Self {
+        Self {
+            word,
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl SyntaxHighlighter for WordHighlighter {
+    fn highlight(&mut self, idx: LineIdx, line: &Line) {
+        let annotations = line
+            .word_ranges()
+            .into_iter()
+            .filter(|range| line.sub_str(range.clone()) == self.word)
+            .map(|range| Annotation {
+                annotation_type: AnnotationType::Occurrence,
+                start: line.grapheme_idx_to_byte_idx(range.start),
+                end: line.grapheme_idx_to_byte_idx(range.end),
+            })
+            .collect();
+        self.highlights.insert(idx, annotations);
+    }
+
+    fn get_annotations(&self, idx: LineIdx) -> Option<&Vec<Annotation>> {
+        self.highlights.get(&idx)
+    }
+}
@@ -1,25 +1,46 @@
-use std::{cmp::min, io::Error};
+use std::{
+    cmp::{max, min},
+    io::Error,
+    iter::once,
+    mem::take,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use super::{
     super::{
-        DocumentStatus, Line, NAME, Position, Size, Terminal, VERSION,
-        command::{Edit, Move},
+        AnnotationType, ClipboardEntry, DocumentStatus, Indentation, Line, NAME, Position, Size,
+        Terminal, Theme, ThemeElement, VERSION,
+        command::{Edit, Fold, Mouse, Move, Select, SelectionMode},
     },
     UIComponent,
 };
 use crate::prelude::*;
 
+mod bookmarks;
 mod buffer;
+mod editor_config;
 mod file_info;
+mod folds;
 mod highlighter;
 mod search_direction;
 mod search_info;
+mod selection;
 
+use bookmarks::Bookmarks;
 use buffer::Buffer;
+use editor_config::FileSettings;
 use file_info::FileInfo;
+use folds::Folds;
 use highlighter::Highlighter;
 use search_direction::SearchDirection;
 use search_info::SearchInfo;
+use selection::Selection;
+
+// Two clicks on the same location within this interval select a word.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+// The number of lines scrolled per mouse wheel step.
+const WHEEL_SCROLL_LINES: usize = 3;
 
 #[derive(Default)]
 pub struct View {
@@ -29,6 +50,22 @@ pub struct View {
     text_location: Location,
     scroll_offset: Position,
     search_info: Option<SearchInfo>,
+    selection: Option<Selection>,
+    // The time and location of the previous click, to detect double clicks.
+    last_click: Option<(Instant, Location)>,
+    indentation: Indentation,
+    // Additional carets, which typing, deleting and movement apply to along with
+    // the primary caret at `text_location`.
+    secondary_carets: Vec<Location>,
+    // Whether spaces, tabs and line ends are drawn as `·`, `→` and `¬`.
+    show_whitespace: bool,
+    // Whether trailing whitespace is stripped on save, unless the editorconfig
+    // settings say otherwise.
+    strip_trailing_whitespace: bool,
+    // The columns marked by a vertical ruler, counted from 0 and ordered.
+    rulers: Vec<ColIdx>,
+    // Whether a guide is drawn at each indentation level of leading whitespace.
+    indent_guides: bool,
 }
 
 impl View {
@@ -40,6 +77,8 @@ impl View {
             file_name: format!("{file_info}"),
             is_modified: self.buffer.is_dirty(),
             file_type: file_info.get_file_type(),
+            mode: None,
+            recording: None,
         }
     }
 
@@ -47,6 +86,59 @@ impl View {
         self.buffer.is_file_loaded()
     }
 
+    pub const fn text_location(&self) -> Location {
+        self.text_location
+    }
+
+    pub fn grapheme_count(&self, line_idx: LineIdx) -> GraphemeIdx {
+        self.buffer.grapheme_count(line_idx)
+    }
+
+    pub fn next_word_start(&self, from: Location) -> Location {
+        self.buffer.next_word_start(from)
+    }
+
+    pub fn set_indentation(&mut self, indentation: Indentation) {
+        self.indentation = indentation;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    pub fn set_show_whitespace(&mut self, show_whitespace: bool) {
+        self.show_whitespace = show_whitespace;
+        self.set_needs_redraw(true);
+    }
+
+    // Toggles showing whitespace and returns whether it is now shown.
+    pub fn toggle_whitespace(&mut self) -> bool {
+        self.set_show_whitespace(!self.show_whitespace);
+        self.show_whitespace
+    }
+
+    pub const fn set_strip_trailing_whitespace(&mut self, strip_trailing_whitespace: bool) {
+        self.strip_trailing_whitespace = strip_trailing_whitespace;
+    }
+
+    // Sets the columns marked by a vertical ruler, counted from 1.
+    pub fn set_rulers(&mut self, mut columns: Vec<ColIdx>) {
+        columns.sort_unstable();
+        columns.dedup();
+        self.rulers = columns
+            .into_iter()
+            .map(|column| column.saturating_sub(1))
+            .collect();
+        self.set_needs_redraw(true);
+    }
+
+    pub fn set_indent_guides(&mut self, indent_guides: bool) {
+        self.indent_guides = indent_guides;
+        self.set_needs_redraw(true);
+    }
+
+    pub const fn has_selection(&self) -> bool {
+        self.selection.is_some()
+    }
+
     // region: search
     pub fn enter_search(&mut self) {
         self.search_info = Some(SearchInfo {
@@ -57,7 +149,13 @@ impl View {
     }
 
     pub fn exit_search(&mut self) {
-        self.search_info = None;
+        if let Some(search_info) = self.search_info.take() {
+            if search_info.prev_location != self.text_location {
+                self.buffer
+                    .bookmarks_mut()
+                    .push_jump(search_info.prev_location);
+            }
+        }
         self.set_needs_redraw(true);
     }
 
@@ -130,37 +228,132 @@ impl View {
 
     pub fn load(&mut self, file_name: &str) -> Result<(), Error> {
         let buffer = Buffer::load(file_name)?;
+        self.indentation = buffer.editor_config().apply_to(self.indentation);
         self.buffer = buffer;
         self.set_needs_redraw(true);
         Ok(())
     }
 
     pub fn save(&mut self) -> Result<(), Error> {
-        self.buffer.save()?;
+        self.buffer.save(self.strip_trailing_whitespace)?;
+        // Saving may have trimmed trailing whitespace
+        self.snap_to_valid_grapheme();
+        self.snap_secondary_carets();
         self.set_needs_redraw(true);
         Ok(())
     }
 
     pub fn save_as(&mut self, file_name: &str) -> Result<(), Error> {
-        self.buffer.save_as(file_name)?;
+        self.buffer
+            .save_as(file_name, self.strip_trailing_whitespace)?;
+        self.indentation = self.buffer.editor_config().apply_to(self.indentation);
+        self.snap_to_valid_grapheme();
+        self.snap_secondary_carets();
         self.set_needs_redraw(true);
         Ok(())
     }
 
+    // Describes the indentation and file format settings in effect.
+    pub fn file_settings(&self) -> String {
+        FileSettings {
+            indentation: self.indentation,
+            editor_config: self.buffer.editor_config(),
+        }
+        .to_string()
+    }
+
     // region end
 
     // region: command handling
 
     pub fn handle_edit_command(&mut self, command: Edit) {
+        let applies_to_each_caret = matches!(
+            command,
+            Edit::Insert(_)
+                | Edit::InsertText(_)
+                | Edit::InsertNewLine
+                | Edit::Indent
+                | Edit::Delete
+                | Edit::DeleteBackward
+                | Edit::DeleteWordForward
+                | Edit::DeleteWordBackward
+        );
+        if applies_to_each_caret
+            && !matches!(command, Edit::Indent)
+            && self.block_ranges().is_some()
+        {
+            // Deleting removes the contents of the block, anything else is applied
+            // to every line of it.
+            self.replace_block_with_carets();
+            if matches!(
+                command,
+                Edit::Delete
+                    | Edit::DeleteBackward
+                    | Edit::DeleteWordForward
+                    | Edit::DeleteWordBackward
+            ) {
+                self.scroll_text_location_into_view();
+                return;
+            }
+        }
+        if applies_to_each_caret && !self.secondary_carets.is_empty() {
+            self.for_each_caret(|view| view.apply_edit_command(command.clone()));
+        } else {
+            self.clear_secondary_carets();
+            self.apply_edit_command(command);
+        }
+        self.scroll_text_location_into_view();
+    }
+
+    fn apply_edit_command(&mut self, command: Edit) {
         match command {
-            Edit::Insert(character) => self.insert_char(character),
+            Edit::Insert(character) => self.type_char(character),
+            Edit::InsertText(text) => self.insert_text(&text),
             Edit::Delete => self.delete(),
             Edit::DeleteBackward => self.delete_backward(),
+            Edit::DeleteWordForward => self.delete_word_forward(),
+            Edit::DeleteWordBackward => self.delete_word_backward(),
             Edit::InsertNewLine => self.insert_newline(),
+            Edit::Indent => self.indent(),
+            Edit::Dedent => self.dedent(),
+            Edit::DeleteLines => self.delete_lines(),
+            Edit::DuplicateLines => self.duplicate_lines(),
+            Edit::MoveLinesUp => self.move_lines_up(),
+            Edit::MoveLinesDown => self.move_lines_down(),
+            Edit::JoinLines => self.join_lines(),
+            Edit::SortLines => self.sort_lines(),
+            Edit::ToggleComment => self.toggle_comment(),
+            // The clipboard is owned by the editor, which calls the respective
+            // methods on the view directly.
+            Edit::Cut
+            | Edit::CutAppend
+            | Edit::Copy
+            | Edit::Paste
+            | Edit::PasteAfter
+            | Edit::CyclePaste => {}
         }
     }
 
     pub fn handle_move_command(&mut self, command: Move) {
+        let prev_location = self.text_location;
+        if self.secondary_carets.is_empty() {
+            self.apply_move_command(command);
+            if is_jump(command) && self.text_location != prev_location {
+                self.buffer.bookmarks_mut().push_jump(prev_location);
+            }
+        } else {
+            self.for_each_caret(|view| view.apply_move_command(command));
+            self.set_needs_redraw(true);
+        }
+        // The current line, word occurrences and matching brackets follow the
+        // caret
+        if self.selection.is_some() || self.text_location != prev_location {
+            self.set_needs_redraw(true);
+        }
+        self.scroll_text_location_into_view();
+    }
+
+    fn apply_move_command(&mut self, command: Move) {
         let Size { height, .. } = self.size;
         match command {
             Move::Up => self.move_up(1),
@@ -171,21 +364,251 @@ impl View {
             Move::PageDown => self.move_down(height.saturating_sub(1)),
             Move::StartOfLine => self.move_to_start_of_line(),
             Move::EndOfLine => self.move_to_end_of_line(),
+            Move::FirstNonBlank => self.move_to_first_non_blank(),
+            Move::WordForward => {
+                self.text_location = self.buffer.next_word_start(self.text_location);
+            }
+            Move::WordBackward => {
+                self.text_location = self.buffer.prev_word_start(self.text_location);
+            }
+            Move::WordEnd => self.text_location = self.buffer.next_word_end(self.text_location),
+            Move::StartOfDocument => self.text_location = Location::default(),
+            Move::EndOfDocument => self.text_location = self.buffer.end_location(),
+            Move::GoToLine(line_idx) => self.move_to_line(line_idx),
+            Move::MatchingBracket => {
+                let all_lines = 0..self.buffer.height();
+                if let Some([_, matching]) = self.buffer.bracket_pair(self.text_location, all_lines)
+                {
+                    self.text_location = matching;
+                }
+            }
+            Move::ToMark(name) => {
+                if let Some(location) = self.buffer.bookmarks().mark(name) {
+                    self.move_to_location(location);
+                }
+            }
+            Move::JumpBack => {
+                let current = self.text_location;
+                if let Some(location) = self.buffer.bookmarks_mut().jump_back(current) {
+                    self.move_to_location(location);
+                }
+            }
+            Move::JumpForward => {
+                if let Some(location) = self.buffer.bookmarks_mut().jump_forward() {
+                    self.move_to_location(location);
+                }
+            }
+        }
+    }
+
+    pub fn set_mark(&mut self, name: char) {
+        let location = self.text_location;
+        self.buffer.bookmarks_mut().set_mark(name, location);
+    }
+
+    pub fn handle_select_command(&mut self, command: Select) {
+        match command {
+            Select::Begin(mode) => {
+                self.secondary_carets.clear();
+                self.selection = Some(Selection {
+                    anchor: self.text_location,
+                    mode,
+                });
+            }
+            Select::Clear => self.selection = None,
+            Select::CollapseToStart => {
+                if let Some(range) = self.selected_range() {
+                    self.text_location = range.start;
+                    self.scroll_text_location_into_view();
+                }
+                self.selection = None;
+            }
+            Select::AddCaretAbove => self.add_caret_vertically(true),
+            Select::AddCaretBelow => self.add_caret_vertically(false),
+            Select::AddCaretAtNextOccurrence => self.add_caret_at_next_occurrence(),
+        }
+        self.set_needs_redraw(true);
+    }
+
+    pub fn handle_mouse_command(&mut self, command: Mouse) {
+        match command {
+            Mouse::Press(position) => self.click(position),
+            Mouse::AddCaret(position) => self.toggle_caret_at(position),
+            Mouse::Drag(position) => self.drag(position, false),
+            Mouse::DragBlock(position) => self.drag(position, true),
+            Mouse::ScrollUp => self.scroll_viewport_up(WHEEL_SCROLL_LINES),
+            Mouse::ScrollDown => self.scroll_viewport_down(WHEEL_SCROLL_LINES),
+        }
+    }
+
+    // region end
+
+    // region: Folding
+
+    pub fn handle_fold_command(&mut self, command: Fold) {
+        let line_idx = self.text_location.line_idx;
+        match command {
+            Fold::Close => self.close_fold(),
+            Fold::Open => {
+                self.buffer.folds_mut().open(line_idx);
+            }
+            Fold::Toggle => {
+                if !self.buffer.folds_mut().open(line_idx) {
+                    self.close_fold();
+                }
+            }
+            Fold::CloseAll => {
+                for region in self.buffer.fold_regions(self.indentation.tab_width) {
+                    self.buffer.folds_mut().close(region);
+                }
+            }
+            Fold::OpenAll => self.buffer.folds_mut().clear(),
         }
+        self.move_out_of_folds();
+        self.set_needs_redraw(true);
         self.scroll_text_location_into_view();
     }
 
+    // Closes the innermost fold containing the caret line, or having it as its
+    // header, which isn't closed yet.
+    fn close_fold(&mut self) {
+        let line_idx = self.text_location.line_idx;
+        let region = self
+            .buffer
+            .fold_regions(self.indentation.tab_width)
+            .into_iter()
+            .filter(|region| {
+                region.start <= line_idx.saturating_add(1)
+                    && line_idx < region.end
+                    && !self
+                        .buffer
+                        .folds()
+                        .is_closed(region.start.saturating_sub(1))
+            })
+            .max_by_key(|region| region.start);
+        if let Some(region) = region {
+            self.buffer.folds_mut().close(region);
+        }
+    }
+
+    // Moves the caret to the header of the fold hiding its line, if any, and
+    // drops secondary carets on hidden lines.
+    fn move_out_of_folds(&mut self) {
+        let folds = self.buffer.folds();
+        let line_idx = self.text_location.line_idx;
+        if folds.is_hidden(line_idx) {
+            // The line in front of the hidden lines is the outermost header
+            self.text_location.line_idx = folds.line_at(folds.row_of(line_idx).saturating_sub(1));
+            self.snap_to_valid_grapheme();
+        }
+        let folds = self.buffer.folds();
+        self.secondary_carets
+            .retain(|caret| !folds.is_hidden(caret.line_idx));
+    }
+
+    // region end
+
+    // region: Mouse handling
+
+    fn click(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        let location = self.position_to_text_location(position);
+        let now = Instant::now();
+        let is_double_click = self.last_click.is_some_and(|(time, last_location)| {
+            last_location == location && now.duration_since(time) <= DOUBLE_CLICK_INTERVAL
+        });
+        self.last_click = (!is_double_click).then_some((now, location));
+        self.selection = None;
+        self.secondary_carets.clear();
+        self.text_location = location;
+        if is_double_click {
+            self.select_word_at(location);
+        }
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    // Extends the selection to the dragged position, starting a new selection at
+    // the caret if there is none. Dragging with `block` set turns the selection
+    // into a block selection.
+    fn drag(&mut self, position: Position, block: bool) {
+        let position = Position {
+            row: min(position.row, self.size.height.saturating_sub(1)),
+            col: position.col,
+        };
+        let anchor = self
+            .selection
+            .map_or(self.text_location, |selection| selection.anchor);
+        let mode = match self.selection {
+            _ if block => SelectionMode::Block,
+            Some(selection) => selection.mode,
+            None => SelectionMode::Character,
+        };
+        self.selection = Some(Selection { anchor, mode });
+        self.text_location = self.position_to_text_location(position);
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    fn select_word_at(&mut self, location: Location) {
+        let Some(range) = self.buffer.word_range_at(location) else {
+            return;
+        };
+        self.selection = Some(Selection {
+            anchor: Location {
+                line_idx: location.line_idx,
+                grapheme_idx: range.start,
+            },
+            mode: SelectionMode::Character,
+        });
+        self.text_location.grapheme_idx = range.end;
+    }
+
     // region end
 
     // region: Text editing
 
+    // Splits the line at the caret. With auto-indent, the new line starts with
+    // the indentation of the current one, one level deeper after the start of a
+    // block.
     fn insert_newline(&mut self) {
+        let indent = if self.indentation.auto_indent {
+            self.indent_for_new_line()
+        } else {
+            String::new()
+        };
+        // Between an empty pair of brackets, the closing bracket moves onto a
+        // line of its own, with the caret on an indented line in between.
+        let closing_indent = self.is_between_pair(|open, close| open != close).then(|| {
+            self.buffer
+                .leading_whitespace(self.text_location.line_idx)
+                .to_string()
+        });
         self.buffer.insert_newline(self.text_location);
         self.handle_move_command(Move::Right);
+        if !indent.is_empty() {
+            self.insert_text(&indent);
+        }
+        if let Some(closing_indent) = closing_indent {
+            let caret = self.text_location;
+            self.buffer.insert_newline(caret);
+            self.buffer.insert_text(
+                &closing_indent,
+                Location {
+                    line_idx: caret.line_idx.saturating_add(1),
+                    grapheme_idx: 0,
+                },
+            );
+        }
         self.set_needs_redraw(true);
     }
 
     fn delete_backward(&mut self) {
+        if self.delete_indent_level_backward() || self.delete_empty_pair() {
+            return;
+        }
         if self.text_location.line_idx != 0 || self.text_location.grapheme_idx != 0 {
             self.handle_move_command(Move::Left);
             self.delete();
@@ -197,6 +620,148 @@ impl View {
         self.set_needs_redraw(true);
     }
 
+    fn delete_word_forward(&mut self) {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let line_end = self.buffer.grapheme_count(line_idx);
+        if grapheme_idx >= line_end {
+            self.delete();
+            return;
+        }
+        let next_word_start = self.buffer.next_word_start(self.text_location);
+        let end = if next_word_start.line_idx == line_idx {
+            next_word_start
+        } else {
+            Location {
+                line_idx,
+                grapheme_idx: line_end,
+            }
+        };
+        self.buffer.delete_range(self.text_location..end);
+        self.set_needs_redraw(true);
+    }
+
+    fn delete_word_backward(&mut self) {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        if grapheme_idx == 0 {
+            self.delete_backward();
+            return;
+        }
+        let start = max(
+            self.buffer.prev_word_start(self.text_location),
+            Location {
+                line_idx,
+                grapheme_idx: 0,
+            },
+        );
+        self.buffer.delete_range(start..self.text_location);
+        self.text_location = start;
+        self.set_needs_redraw(true);
+    }
+
+    fn insert_text(&mut self, text: &str) {
+        self.text_location = self.buffer.insert_text(text, self.text_location);
+        self.set_needs_redraw(true);
+    }
+
+    // Inserts a typed character. For the pairs of the file type, typing the
+    // opening character also inserts the closing one, and typing a closing
+    // character in front of the same character steps over it. Neither happens
+    // within strings and comments.
+    fn type_char(&mut self, character: char) {
+        let pairs = self
+            .buffer
+            .get_file_info()
+            .get_file_type()
+            .auto_close_pairs();
+        if pairs.is_empty() || self.selection.is_some() {
+            self.insert_char(character);
+            return;
+        }
+        let next = self.buffer.grapheme_at(self.text_location);
+        let is_closing = pairs.iter().any(|&(_, close)| close == character);
+        if is_closing && next.is_some_and(|next| is_char(next, character)) {
+            self.handle_move_command(Move::Right);
+            return;
+        }
+        let Some(&(open, close)) = pairs.iter().find(|&&(open, _)| open == character) else {
+            self.insert_char(character);
+            return;
+        };
+        // Only close pairs in front of whitespace or another closing character,
+        // and quotes only when they do not follow a word.
+        let next_allows_close = next.is_none_or(|next| {
+            next.trim().is_empty() || pairs.iter().any(|&(_, close)| is_char(next, close))
+        });
+        let previous_allows_close = open != close
+            || self
+                .text_location
+                .grapheme_idx
+                .checked_sub(1)
+                .and_then(|grapheme_idx| {
+                    self.buffer.grapheme_at(Location {
+                        line_idx: self.text_location.line_idx,
+                        grapheme_idx,
+                    })
+                })
+                .is_none_or(|previous| !previous.chars().any(char::is_alphanumeric));
+        if next_allows_close
+            && previous_allows_close
+            && !self.buffer.is_within_literal_or_comment(self.text_location)
+        {
+            self.insert_char(open);
+            self.buffer.insert_char(close, self.text_location);
+        } else {
+            self.insert_char(character);
+        }
+    }
+
+    // Deletes both characters if the caret is between the opening and closing
+    // character of an empty pair. Returns false otherwise.
+    fn delete_empty_pair(&mut self) -> bool {
+        if self.selection.is_some() || !self.is_between_pair(|_, _| true) {
+            return false;
+        }
+        self.handle_move_command(Move::Left);
+        self.buffer.delete(self.text_location);
+        self.buffer.delete(self.text_location);
+        self.set_needs_redraw(true);
+        true
+    }
+
+    // Whether the caret is between the opening and closing character of an empty
+    // pair of the file type which satisfies the filter.
+    fn is_between_pair(&self, filter: impl Fn(char, char) -> bool) -> bool {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let Some(previous_idx) = grapheme_idx.checked_sub(1) else {
+            return false;
+        };
+        let previous = self.buffer.grapheme_at(Location {
+            line_idx,
+            grapheme_idx: previous_idx,
+        });
+        let next = self.buffer.grapheme_at(self.text_location);
+        let (Some(previous), Some(next)) = (previous, next) else {
+            return false;
+        };
+        self.buffer
+            .get_file_info()
+            .get_file_type()
+            .auto_close_pairs()
+            .iter()
+            .any(|&(open, close)| {
+                filter(open, close) && is_char(previous, open) && is_char(next, close)
+            })
+    }
+
     fn insert_char(&mut self, character: char) {
         let old_len = self.buffer.grapheme_count(self.text_location.line_idx);
         self.buffer.insert_char(character, self.text_location);
@@ -211,10 +776,970 @@ impl View {
 
     // region end
 
+    // region: Indentation
+
+    fn indent(&mut self) {
+        if let Some(lines) = self.selected_lines() {
+            self.indent_lines(lines);
+        } else if self.indentation.soft_tabs {
+            let Location {
+                line_idx,
+                grapheme_idx,
+            } = self.text_location;
+            let col = self
+                .buffer
+                .width_until(line_idx, grapheme_idx, self.indentation.tab_width);
+            let width = self.indentation.width.max(1);
+            self.insert_text(&" ".repeat(width.saturating_sub(col % width)));
+        } else {
+            self.insert_char('\t');
+        }
+    }
+
+    fn dedent(&mut self) {
+        self.dedent_lines(self.affected_lines());
+    }
+
+    // Returns the lines touched by the selection, or the current line.
+    fn affected_lines(&self) -> Range<LineIdx> {
+        let line_idx = self.text_location.line_idx;
+        self.selected_lines()
+            .unwrap_or(line_idx..line_idx.saturating_add(1))
+    }
+
+    // Returns the lines touched by the selection. A selection ending at the
+    // start of a line does not include that line.
+    fn selected_lines(&self) -> Option<Range<LineIdx>> {
+        let Range { start, end } = self.selected_range()?;
+        let end_line_idx = if end.grapheme_idx == 0 && end.line_idx > start.line_idx {
+            end.line_idx
+        } else {
+            end.line_idx.saturating_add(1)
+        };
+        Some(start.line_idx..min(end_line_idx, self.buffer.height()))
+    }
+
+    // Indents every non-empty line in the range by one level.
+    fn indent_lines(&mut self, lines: Range<LineIdx>) {
+        let unit = self.indentation.unit();
+        let unit_len = unit.len();
+        for line_idx in lines {
+            if self.buffer.grapheme_count(line_idx) == 0 {
+                continue;
+            }
+            self.buffer.insert_text(
+                &unit,
+                Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                },
+            );
+            self.adjust_locations_on_line(line_idx, |grapheme_idx| {
+                grapheme_idx.saturating_add(unit_len)
+            });
+        }
+        self.set_needs_redraw(true);
+    }
+
+    // Removes one level of indentation, that is a tab or up to one indentation
+    // width of spaces, from every line in the range.
+    fn dedent_lines(&mut self, lines: Range<LineIdx>) {
+        let width = self.indentation.width;
+        for line_idx in lines {
+            let leading_whitespace = self.buffer.leading_whitespace(line_idx);
+            let removed = if leading_whitespace.starts_with('\t') {
+                1
+            } else {
+                leading_whitespace
+                    .chars()
+                    .take_while(|&character| character == ' ')
+                    .take(width)
+                    .count()
+            };
+            if removed == 0 {
+                continue;
+            }
+            self.buffer.delete_range(
+                Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                }..Location {
+                    line_idx,
+                    grapheme_idx: removed,
+                },
+            );
+            self.adjust_locations_on_line(line_idx, |grapheme_idx| {
+                grapheme_idx.saturating_sub(removed)
+            });
+        }
+        self.set_needs_redraw(true);
+    }
+
+    // Moves the caret and the selection anchor along with text inserted into or
+    // removed from the given line.
+    fn adjust_locations_on_line(
+        &mut self,
+        line_idx: LineIdx,
+        adjust: impl Fn(GraphemeIdx) -> GraphemeIdx,
+    ) {
+        if self.text_location.line_idx == line_idx {
+            self.text_location.grapheme_idx = adjust(self.text_location.grapheme_idx);
+        }
+        if let Some(selection) = &mut self.selection {
+            if selection.anchor.line_idx == line_idx {
+                selection.anchor.grapheme_idx = adjust(selection.anchor.grapheme_idx);
+            }
+        }
+    }
+
+    // Returns the indentation for a line inserted at the caret: the leading
+    // whitespace before the caret, plus one level if the text before the caret
+    // opens a block.
+    fn indent_for_new_line(&self) -> String {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let line_start = Location {
+            line_idx,
+            grapheme_idx: 0,
+        };
+        let indent_end = Location {
+            line_idx,
+            grapheme_idx: min(grapheme_idx, self.buffer.first_non_blank(line_idx)),
+        };
+        let mut indent = self.buffer.text_in_range(line_start..indent_end);
+        let file_type = self.buffer.get_file_info().get_file_type();
+        let opens_block = self
+            .buffer
+            .text_in_range(line_start..self.text_location)
+            .trim_end()
+            .chars()
+            .last()
+            .is_some_and(|character| file_type.opens_block(character));
+        if opens_block {
+            indent.push_str(&self.indentation.unit());
+        }
+        indent
+    }
+
+    // Within the leading whitespace, removes the spaces before the caret back
+    // to the previous indentation level. Returns false if the caret is not
+    // preceded by leading spaces.
+    fn delete_indent_level_backward(&mut self) -> bool {
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        if self.selection.is_some()
+            || grapheme_idx == 0
+            || grapheme_idx > self.buffer.first_non_blank(line_idx)
+        {
+            return false;
+        }
+        let line_start = Location {
+            line_idx,
+            grapheme_idx: 0,
+        };
+        let before = self.buffer.text_in_range(line_start..self.text_location);
+        if !before.ends_with(' ') {
+            return false;
+        }
+        let col = self
+            .buffer
+            .width_until(line_idx, grapheme_idx, self.indentation.tab_width);
+        let width = self.indentation.width.max(1);
+        let previous_level = col.saturating_sub(1) / width * width;
+        let removed = before
+            .chars()
+            .rev()
+            .take_while(|&character| character == ' ')
+            .take(col.saturating_sub(previous_level))
+            .count();
+        let start = Location {
+            line_idx,
+            grapheme_idx: grapheme_idx.saturating_sub(removed),
+        };
+        self.buffer.delete_range(start..self.text_location);
+        self.text_location = start;
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        true
+    }
+
+    // region end
+
+    // region: Line operations
+
+    fn delete_lines(&mut self) {
+        let lines = self.affected_lines();
+        self.buffer.delete_lines(lines.clone());
+        self.selection = None;
+        self.text_location.line_idx = min(lines.start, self.buffer.height().saturating_sub(1));
+        self.snap_to_valid_grapheme();
+        self.set_needs_redraw(true);
+    }
+
+    // Duplicates the affected lines, moving the caret and selection onto the
+    // copy.
+    fn duplicate_lines(&mut self) {
+        let lines = self.affected_lines();
+        let count = lines.len();
+        self.buffer.duplicate_lines(lines);
+        self.adjust_line_indices(|line_idx| line_idx.saturating_add(count));
+        self.set_needs_redraw(true);
+    }
+
+    fn move_lines_up(&mut self) {
+        if self.buffer.move_lines_up(self.affected_lines()) {
+            self.adjust_line_indices(|line_idx| line_idx.saturating_sub(1));
+            self.set_needs_redraw(true);
+        }
+    }
+
+    fn move_lines_down(&mut self) {
+        if self.buffer.move_lines_down(self.affected_lines()) {
+            self.adjust_line_indices(|line_idx| line_idx.saturating_add(1));
+            self.set_needs_redraw(true);
+        }
+    }
+
+    // Joins the selected lines, or the current and the next line, and places the
+    // caret at the last join.
+    fn join_lines(&mut self) {
+        let lines = self.affected_lines();
+        let joins = max(lines.len().saturating_sub(1), 1);
+        for _ in 0..joins {
+            if let Some(location) = self.buffer.join_lines(lines.start) {
+                self.text_location = location;
+            }
+        }
+        self.selection = None;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+        self.set_needs_redraw(true);
+    }
+
+    // Sorts the selected lines and selects them in full.
+    fn sort_lines(&mut self) {
+        let Some(lines) = self.selected_lines() else {
+            return;
+        };
+        self.buffer.sort_lines(lines.clone());
+        let last_line_idx = lines.end.saturating_sub(1);
+        if let Some(selection) = &mut self.selection {
+            selection.anchor = Location {
+                line_idx: lines.start,
+                grapheme_idx: 0,
+            };
+        }
+        self.text_location = Location {
+            line_idx: last_line_idx,
+            grapheme_idx: self.buffer.grapheme_count(last_line_idx),
+        };
+        self.set_needs_redraw(true);
+    }
+
+    // Moves the caret and the selection anchor to other lines, keeping their
+    // grapheme indices.
+    fn adjust_line_indices(&mut self, adjust: impl Fn(LineIdx) -> LineIdx) {
+        self.text_location.line_idx = adjust(self.text_location.line_idx);
+        if let Some(selection) = &mut self.selection {
+            selection.anchor.line_idx = adjust(selection.anchor.line_idx);
+        }
+    }
+
+    // region end
+
+    // region: Comments
+
+    // Comments out the affected lines, or uncomments them if every non-blank
+    // line among them is commented. Line comments are preferred over block
+    // comments, and markers are aligned to the smallest indentation.
+    fn toggle_comment(&mut self) {
+        let file_type = self.buffer.get_file_info().get_file_type();
+        let (open, close) = match (file_type.line_comment(), file_type.block_comment()) {
+            (Some(prefix), _) => (prefix, ""),
+            (None, Some(delimiters)) => delimiters,
+            (None, None) => return,
+        };
+        let lines: Vec<LineIdx> = self
+            .affected_lines()
+            .filter(|&line_idx| {
+                self.buffer.first_non_blank(line_idx) < self.buffer.grapheme_count(line_idx)
+            })
+            .collect();
+        let all_commented = lines
+            .iter()
+            .all(|&line_idx| self.is_commented(line_idx, open, close));
+        if all_commented {
+            for line_idx in lines {
+                self.uncomment_line(line_idx, open, close);
+            }
+        } else {
+            let col = lines
+                .iter()
+                .map(|&line_idx| self.buffer.first_non_blank(line_idx))
+                .min()
+                .unwrap_or_default();
+            for line_idx in lines {
+                self.comment_line(line_idx, col, open, close);
+            }
+        }
+        self.set_needs_redraw(true);
+    }
+
+    fn is_commented(&self, line_idx: LineIdx, open: &str, close: &str) -> bool {
+        let text = self.buffer.text_in_range(
+            Location {
+                line_idx,
+                grapheme_idx: self.buffer.first_non_blank(line_idx),
+            }..Location {
+                line_idx,
+                grapheme_idx: self.buffer.grapheme_count(line_idx),
+            },
+        );
+        let text = text.trim_end();
+        text.starts_with(open) && text.ends_with(close) && text.len() >= open.len() + close.len()
+    }
+
+    fn comment_line(&mut self, line_idx: LineIdx, col: GraphemeIdx, open: &str, close: &str) {
+        if !close.is_empty() {
+            self.buffer.insert_text(
+                &format!(" {close}"),
+                Location {
+                    line_idx,
+                    grapheme_idx: self.buffer.grapheme_count(line_idx),
+                },
+            );
+        }
+        let marker = format!("{open} ");
+        let marker_len = marker.len();
+        self.buffer.insert_text(
+            &marker,
+            Location {
+                line_idx,
+                grapheme_idx: col,
+            },
+        );
+        self.adjust_locations_on_line(line_idx, |grapheme_idx| {
+            if grapheme_idx >= col {
+                grapheme_idx.saturating_add(marker_len)
+            } else {
+                grapheme_idx
+            }
+        });
+    }
+
+    // Removes the comment markers from the line, along with a space following
+    // the opening marker and a space preceding the closing marker.
+    fn uncomment_line(&mut self, line_idx: LineIdx, open: &str, close: &str) {
+        let start = self.buffer.first_non_blank(line_idx);
+        let line_end = Location {
+            line_idx,
+            grapheme_idx: self.buffer.grapheme_count(line_idx),
+        };
+        let text = self.buffer.text_in_range(
+            Location {
+                line_idx,
+                grapheme_idx: start,
+            }..line_end,
+        );
+        if !close.is_empty() {
+            let content = text.trim_end();
+            let trailing_len = text.len().saturating_sub(content.len());
+            let content = content.strip_suffix(close).unwrap_or(content);
+            let close_len = if content.ends_with(' ') && content.len() > open.len() {
+                close.len().saturating_add(1)
+            } else {
+                close.len()
+            };
+            let close_end = line_end.grapheme_idx.saturating_sub(trailing_len);
+            self.buffer.delete_range(
+                Location {
+                    line_idx,
+                    grapheme_idx: close_end.saturating_sub(close_len),
+                }..Location {
+                    line_idx,
+                    grapheme_idx: close_end,
+                },
+            );
+        }
+        let removed = if text
+            .strip_prefix(open)
+            .is_some_and(|rest| rest.starts_with(' '))
+        {
+            open.len().saturating_add(1)
+        } else {
+            open.len()
+        };
+        self.buffer.delete_range(
+            Location {
+                line_idx,
+                grapheme_idx: start,
+            }..Location {
+                line_idx,
+                grapheme_idx: start.saturating_add(removed),
+            },
+        );
+        let line_len = self.buffer.grapheme_count(line_idx);
+        self.adjust_locations_on_line(line_idx, |grapheme_idx| {
+            let adjusted = if grapheme_idx >= start.saturating_add(removed) {
+                grapheme_idx.saturating_sub(removed)
+            } else {
+                min(grapheme_idx, start)
+            };
+            min(adjusted, line_len)
+        });
+    }
+
+    // region end
+
+    // region: Multiple carets
+
+    pub fn clear_secondary_carets(&mut self) {
+        if !self.secondary_carets.is_empty() {
+            self.secondary_carets.clear();
+            self.set_needs_redraw(true);
+        }
+    }
+
+    // Applies the function to every caret in turn, each time with the caret at
+    // `text_location`. Carets are processed from the end of the buffer towards
+    // its start, so that an edit never changes the text behind the carets which
+    // were processed before. Those carets are thus tracked by their distance to
+    // the end of the buffer and of their line. Carets which end up at the same
+    // location are merged.
+    fn for_each_caret(&mut self, mut apply: impl FnMut(&mut Self)) {
+        let primary = self.text_location;
+        let scroll_offset = self.scroll_offset;
+        let mut carets = take(&mut self.secondary_carets);
+        carets.push(primary);
+        carets.sort_unstable();
+        carets.dedup();
+        let mut processed: Vec<(Location, bool)> = Vec::with_capacity(carets.len());
+        for caret in carets.into_iter().rev() {
+            let height = self.buffer.height();
+            let distances: Vec<(usize, GraphemeIdx, bool)> = processed
+                .iter()
+                .map(|&(location, is_primary)| {
+                    (
+                        height.saturating_sub(location.line_idx),
+                        self.buffer
+                            .grapheme_count(location.line_idx)
+                            .saturating_sub(location.grapheme_idx),
+                        is_primary,
+                    )
+                })
+                .collect();
+            self.text_location = caret;
+            apply(self);
+            let height = self.buffer.height();
+            processed = distances
+                .into_iter()
+                .map(|(lines_to_end, graphemes_to_line_end, is_primary)| {
+                    let line_idx = height.saturating_sub(lines_to_end);
+                    let grapheme_idx = self
+                        .buffer
+                        .grapheme_count(line_idx)
+                        .saturating_sub(graphemes_to_line_end);
+                    (
+                        Location {
+                            line_idx,
+                            grapheme_idx,
+                        },
+                        is_primary,
+                    )
+                })
+                .collect();
+            processed.push((self.text_location, caret == primary));
+        }
+        for (location, is_primary) in processed {
+            if is_primary {
+                self.text_location = location;
+            } else {
+                self.secondary_carets.push(location);
+            }
+        }
+        self.merge_carets();
+        self.scroll_offset = scroll_offset;
+        self.set_needs_redraw(true);
+    }
+
+    fn snap_secondary_carets(&mut self) {
+        for caret in &mut self.secondary_carets {
+            caret.grapheme_idx = min(
+                caret.grapheme_idx,
+                self.buffer.grapheme_count(caret.line_idx),
+            );
+        }
+        self.merge_carets();
+    }
+
+    fn merge_carets(&mut self) {
+        let primary = self.text_location;
+        self.secondary_carets.sort_unstable();
+        self.secondary_carets.dedup();
+        self.secondary_carets.retain(|&caret| caret != primary);
+    }
+
+    // Moves the primary caret to the location, keeping a secondary caret at its
+    // previous location.
+    fn add_caret(&mut self, location: Location) {
+        self.selection = None;
+        self.secondary_carets.push(self.text_location);
+        self.text_location = location;
+        self.merge_carets();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    // Adds a caret at the clicked location, or removes the secondary caret
+    // there.
+    fn toggle_caret_at(&mut self, position: Position) {
+        if position.row >= self.size.height {
+            return;
+        }
+        let location = self.position_to_text_location(position);
+        if self.secondary_carets.contains(&location) {
+            self.secondary_carets.retain(|&caret| caret != location);
+            self.set_needs_redraw(true);
+        } else {
+            self.add_caret(location);
+        }
+    }
+
+    fn add_caret_vertically(&mut self, above: bool) {
+        let tab_width = self.indentation.tab_width;
+        let Location {
+            line_idx,
+            grapheme_idx,
+        } = self.text_location;
+        let col = self.buffer.width_until(line_idx, grapheme_idx, tab_width);
+        let lines = self
+            .secondary_carets
+            .iter()
+            .chain(once(&self.text_location))
+            .map(|caret| caret.line_idx);
+        let folds = self.buffer.folds();
+        let line_idx = if above {
+            match lines.min() {
+                Some(topmost) if topmost > 0 => {
+                    folds.line_at(folds.row_of(topmost).saturating_sub(1))
+                }
+                _ => return,
+            }
+        } else {
+            match lines
+                .max()
+                .map(|bottommost| folds.line_at(folds.row_of(bottommost).saturating_add(1)))
+            {
+                Some(below) if below < self.buffer.height() => below,
+                _ => return,
+            }
+        };
+        self.add_caret(Location {
+            line_idx,
+            grapheme_idx: self.buffer.grapheme_idx_at(line_idx, col, tab_width),
+        });
+    }
+
+    // Adds a caret at the next occurrence of the word under or in front of the
+    // primary caret, at the same offset within the word. Occurrences are
+    // searched behind the primary caret, wrapping around at the end of the
+    // buffer.
+    fn add_caret_at_next_occurrence(&mut self) {
+        let primary = self.text_location;
+        let Some(range) = self.word_range_at_caret() else {
+            return;
+        };
+        let word_start = Location {
+            line_idx: primary.line_idx,
+            grapheme_idx: range.start,
+        };
+        let word = self.buffer.text_in_range(
+            word_start..Location {
+                line_idx: primary.line_idx,
+                grapheme_idx: range.end,
+            },
+        );
+        let offset = primary.grapheme_idx.saturating_sub(range.start);
+        let mut from = Location {
+            line_idx: primary.line_idx,
+            grapheme_idx: range.end,
+        };
+        while let Some(found) = self.buffer.search_forward(&word, from) {
+            if found == word_start {
+                return;
+            }
+            let is_whole_word = self.buffer.word_range_at(found).is_some_and(|found_range| {
+                found_range.start == found.grapheme_idx && found_range.len() == range.len()
+            });
+            let caret = Location {
+                line_idx: found.line_idx,
+                grapheme_idx: found.grapheme_idx.saturating_add(offset),
+            };
+            if is_whole_word && !self.secondary_carets.contains(&caret) {
+                self.add_caret(caret);
+                return;
+            }
+            from = Location {
+                line_idx: found.line_idx,
+                grapheme_idx: found.grapheme_idx.saturating_add(1),
+            };
+        }
+    }
+
+    // Returns the range of the word under or in front of the primary caret.
+    fn word_range_at_caret(&self) -> Option<Range<GraphemeIdx>> {
+        let primary = self.text_location;
+        self.buffer.word_range_at(primary).or_else(|| {
+            let grapheme_idx = primary.grapheme_idx.checked_sub(1)?;
+            self.buffer.word_range_at(Location {
+                line_idx: primary.line_idx,
+                grapheme_idx,
+            })
+        })
+    }
+
+    // Returns the identifier under or in front of the primary caret. Runs of
+    // punctuation, which also count as words, are left out.
+    fn word_at_caret(&self) -> Option<String> {
+        let range = self.word_range_at_caret()?;
+        let line_idx = self.text_location.line_idx;
+        let word = self.buffer.text_in_range(
+            Location {
+                line_idx,
+                grapheme_idx: range.start,
+            }..Location {
+                line_idx,
+                grapheme_idx: range.end,
+            },
+        );
+        word.chars()
+            .next()
+            .is_some_and(|first| first.is_alphanumeric() || first == '_')
+            .then_some(word)
+    }
+
+    // region end
+
+    // region: Selection & clipboard
+
+    // Returns the selected range, or None if nothing is selected. For line
+    // selections, the range spans from the start of the first line to the start
+    // of the line following the selection.
+    fn selected_range(&self) -> Option<Range<Location>> {
+        let selection = self.selection?;
+        let start = min(selection.anchor, self.text_location);
+        let end = max(selection.anchor, self.text_location);
+        let range = match selection.mode {
+            SelectionMode::Character => start..end,
+            SelectionMode::Inclusive => {
+                let end = if end.grapheme_idx < self.buffer.grapheme_count(end.line_idx) {
+                    Location {
+                        line_idx: end.line_idx,
+                        grapheme_idx: end.grapheme_idx.saturating_add(1),
+                    }
+                } else if end.line_idx.saturating_add(1) < self.buffer.height() {
+                    Location {
+                        line_idx: end.line_idx.saturating_add(1),
+                        grapheme_idx: 0,
+                    }
+                } else {
+                    end
+                };
+                start..end
+            }
+            SelectionMode::Line => {
+                Location {
+                    line_idx: start.line_idx,
+                    grapheme_idx: 0,
+                }..Location {
+                    line_idx: end.line_idx.saturating_add(1),
+                    grapheme_idx: 0,
+                }
+            }
+            SelectionMode::Block | SelectionMode::InclusiveBlock => {
+                let rows = self.block_ranges()?;
+                rows.first()?.start..rows.last()?.end
+            }
+        };
+        Some(range)
+    }
+
+    // Returns the selected range on each line of a block selection, or None if
+    // the selection isn't a block. The block spans the screen columns between
+    // the anchor and the caret, so that it stays rectangular with tabs and
+    // double-width graphemes. Graphemes partially covered at the left edge are
+    // included, those at the right edge are not.
+    fn block_ranges(&self) -> Option<Vec<Range<Location>>> {
+        let selection = self.selection?;
+        let inclusive = match selection.mode {
+            SelectionMode::Block => false,
+            SelectionMode::InclusiveBlock => true,
+            SelectionMode::Character | SelectionMode::Inclusive | SelectionMode::Line => {
+                return None;
+            }
+        };
+        let tab_width = self.indentation.tab_width;
+        let columns = |location: Location| {
+            let Location {
+                line_idx,
+                grapheme_idx,
+            } = location;
+            let start = self.buffer.width_until(line_idx, grapheme_idx, tab_width);
+            let end = if inclusive {
+                self.buffer
+                    .width_until(line_idx, grapheme_idx.saturating_add(1), tab_width)
+            } else {
+                start
+            };
+            start..end
+        };
+        let anchor = columns(selection.anchor);
+        let caret = columns(self.text_location);
+        let left = min(anchor.start, caret.start);
+        let right = max(anchor.end, caret.end);
+        let top = min(selection.anchor.line_idx, self.text_location.line_idx);
+        let bottom = max(selection.anchor.line_idx, self.text_location.line_idx);
+        let rows = (top..=bottom)
+            .map(|line_idx| {
+                let start = self.buffer.grapheme_idx_at(line_idx, left, tab_width);
+                let end = max(
+                    start,
+                    self.buffer.grapheme_idx_at(line_idx, right, tab_width),
+                );
+                Location {
+                    line_idx,
+                    grapheme_idx: start,
+                }..Location {
+                    line_idx,
+                    grapheme_idx: end,
+                }
+            })
+            .collect();
+        Some(rows)
+    }
+
+    // Returns the ranges to highlight as selected.
+    fn selection_ranges(&self) -> Vec<Range<Location>> {
+        self.block_ranges()
+            .or_else(|| self.selected_range().map(|range| vec![range]))
+            .unwrap_or_default()
+    }
+
+    // Deletes the contents of the block selection and places a caret at its left
+    // edge on every line, with the primary caret staying on its line.
+    fn replace_block_with_carets(&mut self) {
+        let Some(rows) = self.block_ranges() else {
+            return;
+        };
+        let caret_line_idx = self.text_location.line_idx;
+        self.delete_selection();
+        self.secondary_carets = rows.iter().map(|row| row.start).collect();
+        if let Some(row) = rows.iter().find(|row| row.start.line_idx == caret_line_idx) {
+            self.text_location = row.start;
+        }
+        self.merge_carets();
+    }
+
+    fn is_line_selection(&self) -> bool {
+        self.selection
+            .is_some_and(|selection| selection.mode == SelectionMode::Line)
+    }
+
+    fn selected_entry(&self) -> Option<ClipboardEntry> {
+        if let Some(rows) = self.block_ranges() {
+            if rows.iter().all(Range::is_empty) {
+                return None;
+            }
+            let text = rows
+                .into_iter()
+                .map(|row| self.buffer.text_in_range(row))
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Some(ClipboardEntry {
+                text,
+                linewise: false,
+                block: true,
+            });
+        }
+        let range = self.selected_range()?;
+        if range.start >= range.end {
+            return None;
+        }
+        let linewise = self.is_line_selection();
+        let text = if linewise {
+            let last_line_idx = range.end.line_idx.saturating_sub(1);
+            let end = Location {
+                line_idx: last_line_idx,
+                grapheme_idx: self.buffer.grapheme_count(last_line_idx),
+            };
+            format!("{}\n", self.buffer.text_in_range(range.start..end))
+        } else {
+            self.buffer.text_in_range(range)
+        };
+        Some(ClipboardEntry {
+            text,
+            linewise,
+            block: false,
+        })
+    }
+
+    pub fn copy_selection(&self) -> Option<ClipboardEntry> {
+        self.selected_entry()
+    }
+
+    pub fn cut_selection(&mut self) -> Option<ClipboardEntry> {
+        let entry = self.selected_entry()?;
+        self.delete_selection();
+        Some(entry)
+    }
+
+    fn delete_selection(&mut self) {
+        if let Some(rows) = self.block_ranges() {
+            for row in rows.iter().rev() {
+                self.buffer.delete_range(row.clone());
+            }
+            self.selection = None;
+            if let Some(row) = rows.first() {
+                self.text_location = row.start;
+            }
+            self.scroll_text_location_into_view();
+            self.set_needs_redraw(true);
+            return;
+        }
+        let Some(mut range) = self.selected_range() else {
+            return;
+        };
+        if self.is_line_selection() && range.end.line_idx >= self.buffer.height() {
+            // The selection includes the last line: Remove the line break in front of the
+            // selection instead of the one behind it, so that no empty line remains.
+            if let Some(line_idx) = range.start.line_idx.checked_sub(1) {
+                range.start = Location {
+                    line_idx,
+                    grapheme_idx: self.buffer.grapheme_count(line_idx),
+                };
+            }
+            range.end = self.buffer.end_location();
+        }
+        self.buffer.delete_range(range.clone());
+        self.selection = None;
+        self.text_location = range.start;
+        self.snap_to_valid_line();
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+    }
+
+    // Pastes the given entry at the caret, replacing the selection if present.
+    // If `after` is set, the entry is pasted behind the grapheme under the caret,
+    // or below the caret line for linewise entries. Returns the range of the
+    // pasted text, which is empty for blocks, as they can't be replaced as a
+    // whole.
+    pub fn paste(&mut self, entry: &ClipboardEntry, after: bool) -> Range<Location> {
+        self.clear_secondary_carets();
+        self.delete_selection();
+        let range = if entry.linewise {
+            let line_idx = if after {
+                min(
+                    self.text_location.line_idx.saturating_add(1),
+                    self.buffer.height(),
+                )
+            } else {
+                self.text_location.line_idx
+            };
+            let range = if line_idx < self.buffer.height() {
+                let start = Location {
+                    line_idx,
+                    grapheme_idx: 0,
+                };
+                start..self.buffer.insert_text(&entry.text, start)
+            } else {
+                let text = entry.text.strip_suffix('\n').unwrap_or(&entry.text);
+                let text = if self.buffer.is_empty() {
+                    text.to_string()
+                } else {
+                    format!("\n{text}")
+                };
+                let start = self.buffer.end_location();
+                start..self.buffer.insert_text(&text, start)
+            };
+            self.move_to_line(line_idx);
+            range
+        } else {
+            let mut start = self.text_location;
+            if after && start.grapheme_idx < self.buffer.grapheme_count(start.line_idx) {
+                start.grapheme_idx = start.grapheme_idx.saturating_add(1);
+            }
+            if entry.block {
+                self.paste_block(&entry.text, start);
+                self.text_location = start;
+                start..start
+            } else {
+                self.text_location = self.buffer.insert_text(&entry.text, start);
+                start..self.text_location
+            }
+        };
+        self.scroll_text_location_into_view();
+        self.set_needs_redraw(true);
+        range
+    }
+
+    // Inserts the lines of the text below each other, each at the screen column
+    // of the given location. Lines are added at the end of the buffer, and
+    // padded with spaces where they end left of the column.
+    fn paste_block(&mut self, text: &str, at: Location) {
+        let tab_width = self.indentation.tab_width;
+        let col = self
+            .buffer
+            .width_until(at.line_idx, at.grapheme_idx, tab_width);
+        for (offset, row) in text.split('\n').enumerate() {
+            let line_idx = at.line_idx.saturating_add(offset);
+            if line_idx >= self.buffer.height() {
+                self.buffer.insert_newline(self.buffer.end_location());
+            }
+            let line_end = Location {
+                line_idx,
+                grapheme_idx: self.buffer.grapheme_count(line_idx),
+            };
+            let width = self
+                .buffer
+                .width_until(line_idx, line_end.grapheme_idx, tab_width);
+            if width < col && !row.is_empty() {
+                self.buffer
+                    .insert_text(&" ".repeat(col.saturating_sub(width)), line_end);
+            }
+            let grapheme_idx = self.buffer.grapheme_idx_at(line_idx, col, tab_width);
+            self.buffer.insert_text(
+                row,
+                Location {
+                    line_idx,
+                    grapheme_idx,
+                },
+            );
+        }
+    }
+
+    // Replaces previously pasted text with the given entry, returning the range
+    // of the newly pasted text.
+    pub fn replace_pasted(
+        &mut self,
+        range: Range<Location>,
+        entry: &ClipboardEntry,
+    ) -> Range<Location> {
+        self.selection = None;
+        self.buffer.delete_range(range.clone());
+        self.text_location = range.start;
+        self.paste(entry, false)
+    }
+
+    // region end
+
     // region: Rendering
 
-    fn render_line(at: RowIdx, line_text: &str) -> Result<(), Error> {
-        Terminal::print_row(at, line_text)
+    fn render_line(at: RowIdx, line_text: &str, theme: &Theme) -> Result<(), Error> {
+        Terminal::print_styled_row(at, line_text, &theme.style(ThemeElement::Gutter))
     }
 
     fn build_welcome_message(width: usize) -> String {
@@ -266,12 +1791,35 @@ impl View {
         }
     }
 
+    // Opens the folds hiding the caret line, then scrolls it into view.
     fn scroll_text_location_into_view(&mut self) {
+        let line_idx = self.text_location.line_idx;
+        if self.buffer.folds_mut().reveal(line_idx) {
+            self.set_needs_redraw(true);
+        }
         let Position { row, col } = self.text_location_to_position();
         self.scroll_vertically(row);
         self.scroll_horizontally(col);
     }
 
+    // Scrolls the viewport without moving the caret.
+    fn scroll_viewport_up(&mut self, lines: usize) {
+        self.scroll_offset.row = self.scroll_offset.row.saturating_sub(lines);
+        self.set_needs_redraw(true);
+    }
+
+    // Scrolls the viewport without moving the caret, until the last line is at
+    // the top.
+    fn scroll_viewport_down(&mut self, lines: usize) {
+        let last_row = self
+            .buffer
+            .folds()
+            .row_of(self.buffer.height())
+            .saturating_sub(1);
+        self.scroll_offset.row = min(self.scroll_offset.row.saturating_add(lines), last_row);
+        self.set_needs_redraw(true);
+    }
+
     fn center_text_location(&mut self) {
         let Size { height, width } = self.size;
         let Position { row, col } = self.text_location_to_position();
@@ -286,31 +1834,71 @@ impl View {
 
     // region: Location and Position Handing
 
-    pub fn caret_position(&self) -> Position {
-        self.text_location_to_position()
-            .saturating_sub(self.scroll_offset)
+    // Returns the caret position on screen, or None if the caret has been
+    // scrolled out of view.
+    pub fn caret_position(&self) -> Option<Position> {
+        let position = self.text_location_to_position();
+        let top = self.scroll_offset.row;
+        (top..top.saturating_add(self.size.height))
+            .contains(&position.row)
+            .then(|| position.saturating_sub(self.scroll_offset))
     }
 
+    // Returns the position of the caret within the document, counting only
+    // lines which aren't hidden by folds.
     fn text_location_to_position(&self) -> Position {
-        let row = self.text_location.line_idx;
-        debug_assert!(row.saturating_sub(1) <= self.buffer.height());
-        let col = self
+        let line_idx = self.text_location.line_idx;
+        debug_assert!(line_idx.saturating_sub(1) <= self.buffer.height());
+        let col = self.buffer.width_until(
+            line_idx,
+            self.text_location.grapheme_idx,
+            self.indentation.tab_width,
+        );
+        Position {
+            col,
+            row: self.buffer.folds().row_of(line_idx),
+        }
+    }
+
+    // Maps a position within the view to the location of the grapheme drawn
+    // there. Positions below the last line map to the end of the buffer.
+    fn position_to_text_location(&self, position: Position) -> Location {
+        let line_idx = self
             .buffer
-            .width_until(row, self.text_location.grapheme_idx);
-        Position { col, row }
+            .folds()
+            .line_at(position.row.saturating_add(self.scroll_offset.row));
+        if line_idx >= self.buffer.height() {
+            return self.buffer.end_location();
+        }
+        let col = position.col.saturating_add(self.scroll_offset.col);
+        Location {
+            line_idx,
+            grapheme_idx: self
+                .buffer
+                .grapheme_idx_at(line_idx, col, self.indentation.tab_width),
+        }
     }
 
     // region end
 
     // region: text location movement
 
+    // Moves up by the given number of visible lines, skipping folded ones.
     fn move_up(&mut self, step: usize) {
-        self.text_location.line_idx = self.text_location.line_idx.saturating_sub(step);
+        let folds = self.buffer.folds();
+        let row = folds
+            .row_of(self.text_location.line_idx)
+            .saturating_sub(step);
+        self.text_location.line_idx = folds.line_at(row);
         self.snap_to_valid_grapheme();
     }
 
     fn move_down(&mut self, step: usize) {
-        self.text_location.line_idx = self.text_location.line_idx.saturating_add(step);
+        let folds = self.buffer.folds();
+        let row = folds
+            .row_of(self.text_location.line_idx)
+            .saturating_add(step);
+        self.text_location.line_idx = folds.line_at(row);
         self.snap_to_valid_grapheme();
         self.snap_to_valid_line();
     }
@@ -347,8 +1935,23 @@ impl View {
     }
 
     fn move_to_end_of_line(&mut self) {
-        self.text_location.grapheme_idx =
-            self.buffer.grapheme_count(self.text_location.grapheme_idx);
+        self.text_location.grapheme_idx = self.buffer.grapheme_count(self.text_location.line_idx);
+    }
+
+    fn move_to_first_non_blank(&mut self) {
+        self.text_location.grapheme_idx = self.buffer.first_non_blank(self.text_location.line_idx);
+    }
+
+    // Moves to the location, which may have been left invalid by edits.
+    fn move_to_location(&mut self, location: Location) {
+        self.text_location = location;
+        self.snap_to_valid_line();
+        self.snap_to_valid_grapheme();
+    }
+
+    fn move_to_line(&mut self, line_idx: LineIdx) {
+        self.text_location.line_idx = min(line_idx, self.buffer.height().saturating_sub(1));
+        self.move_to_first_non_blank();
     }
 
     // Ensures self.location.grapheme_idx points to a valid grapheme index by
@@ -383,41 +1986,141 @@ impl UIComponent for View {
         self.size = size;
     }
 
-    fn draw(&mut self, origin_row: RowIdx) -> Result<(), std::io::Error> {
+    fn draw(&mut self, origin_row: RowIdx, theme: &Theme) -> Result<(), std::io::Error> {
         let Size { height, width } = self.size;
         let end_y = origin_row.saturating_add(height);
         let top_third = height.div_ceil(3);
         let scroll_top = self.scroll_offset.row;
+        let folds = self.buffer.folds();
+        let first_line_idx = folds.line_at(scroll_top);
+        let end_line_idx = folds.line_at(scroll_top.saturating_add(height));
+        // The bracket matching the one at the caret is searched for up to a
+        // screen above and below the visible lines.
+        let brackets = self.buffer.bracket_pair(
+            self.text_location,
+            first_line_idx.saturating_sub(height)..end_line_idx.saturating_add(height),
+        );
+        self.buffer.highlight_syntax_until(end_line_idx);
         let query = self
             .search_info
             .as_ref()
             .and_then(|search_info| search_info.query.as_deref());
         let selected_match = query.is_some().then_some(self.text_location);
+        // Occurrences would get in the way of search results and selections
+        let occurrences_of = (query.is_none() && self.selection.is_none())
+            .then(|| self.word_at_caret())
+            .flatten();
         let mut highlighter = Highlighter::new(
             query,
             selected_match,
-            self.buffer.get_file_info().get_file_type(),
+            occurrences_of,
+            self.selection_ranges(),
+            brackets,
+            &self.secondary_carets,
+            self.buffer.syntax(),
         );
-        for current_row in 0..end_y.saturating_add(scroll_top) {
-            self.buffer.highlight(current_row, &mut highlighter);
+        highlighter.highlight_current_line(self.text_location.line_idx);
+        highlighter.highlight_whitespace(self.show_whitespace, self.text_location);
+        highlighter.highlight_rulers(self.rulers.clone(), self.indentation.tab_width);
+        let indent_guides = self.indent_guides.then_some(self.indentation.width);
+        let folds = self.buffer.folds();
+        for line_idx in first_line_idx..=end_line_idx {
+            self.buffer.highlight(line_idx, &mut highlighter);
         }
         for current_row in origin_row..end_y {
-            let line_idx = current_row
-                .saturating_sub(origin_row)
-                .saturating_add(scroll_top);
+            let line_idx = folds.line_at(
+                current_row
+                    .saturating_sub(origin_row)
+                    .saturating_add(scroll_top),
+            );
             let left = self.scroll_offset.col;
             let right = self.scroll_offset.col.saturating_add(width);
-            if let Some(annotated_string) =
-                self.buffer
-                    .get_highlighted_substring(line_idx, left..right, &highlighter)
-            {
-                Terminal::print_annotated_row(current_row, &annotated_string)?;
+            if let Some(mut annotated_string) = self.buffer.get_highlighted_substring(
+                line_idx,
+                left..right,
+                &highlighter,
+                self.indentation.tab_width,
+                self.show_whitespace,
+                indent_guides,
+            ) {
+                // Secondary carets behind the end of the line have no grapheme to
+                // highlight, so they are drawn on a space, or on the line end
+                // marker if whitespace is shown.
+                let line_end = Location {
+                    line_idx,
+                    grapheme_idx: self.buffer.grapheme_count(line_idx),
+                };
+                let line_width = self.buffer.width_until(
+                    line_idx,
+                    line_end.grapheme_idx,
+                    self.indentation.tab_width,
+                );
+                let mut end_col = line_width;
+                if (left..right).contains(&line_width) {
+                    let line_end_marker = if self.show_whitespace { "¬" } else { " " };
+                    if self.secondary_carets.contains(&line_end) {
+                        annotated_string
+                            .push_annotated(line_end_marker, AnnotationType::SecondaryCaret);
+                        end_col = end_col.saturating_add(1);
+                    } else if self.show_whitespace {
+                        annotated_string
+                            .push_annotated(line_end_marker, AnnotationType::Whitespace);
+                        end_col = end_col.saturating_add(1);
+                    }
+                }
+                if let Some(hidden) = folds.hidden_after(line_idx) {
+                    if (left..right).contains(&end_col) {
+                        let unit = if hidden == 1 { "line" } else { "lines" };
+                        let marker: String = format!(" ⋯ {hidden} {unit}")
+                            .chars()
+                            .take(right.saturating_sub(end_col))
+                            .collect();
+                        end_col = end_col.saturating_add(marker.chars().count());
+                        annotated_string.push_annotated(&marker, AnnotationType::FoldMarker);
+                    }
+                }
+                // Rulers behind the end of the line are drawn on padding
+                let mut drawn_until = max(end_col, left);
+                for &ruler in self.rulers.iter().filter(|&&ruler| ruler < right) {
+                    if ruler >= drawn_until {
+                        annotated_string.push_str(&" ".repeat(ruler.saturating_sub(drawn_until)));
+                        annotated_string.push_annotated(" ", AnnotationType::Ruler);
+                        drawn_until = ruler.saturating_add(1);
+                    }
+                }
+                // The background of the line fills the row
+                if let Some(background) = highlighter.line_background(line_idx) {
+                    annotated_string.push_str(&" ".repeat(right.saturating_sub(drawn_until)));
+                    annotated_string.add_background(background);
+                }
+                Terminal::print_annotated_row(current_row, &annotated_string, theme)?;
             } else if current_row == top_third && self.buffer.is_empty() {
-                Self::render_line(current_row, &Self::build_welcome_message(width))?;
+                Self::render_line(current_row, &Self::build_welcome_message(width), theme)?;
             } else {
-                Self::render_line(current_row, "~")?;
+                Self::render_line(current_row, "~", theme)?;
             }
         }
         Ok(())
     }
 }
+
+// Whether the move is a jump, which leaves its starting location in the jump
+// list.
+const fn is_jump(command: Move) -> bool {
+    matches!(
+        command,
+        Move::PageUp
+            | Move::PageDown
+            | Move::StartOfDocument
+            | Move::EndOfDocument
+            | Move::GoToLine(_)
+            | Move::MatchingBracket
+            | Move::ToMark(_)
+    )
+}
+
+// Whether the grapheme consists of just the given character.
+fn is_char(grapheme: &str, character: char) -> bool {
+    let mut chars = grapheme.chars();
+    chars.next() == Some(character) && chars.next().is_none()
+}
@@ -0,0 +1,7 @@
+use crate::{editor::command::SelectionMode, prelude::*};
+
+#[derive(Clone, Copy)]
+pub struct Selection {
+    pub anchor: Location,
+    pub mode: SelectionMode,
+}
@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+// A command entered on the `:` command line.
+#[derive(Eq, PartialEq, Debug)]
+pub enum ExCommand {
+    Write(Option<String>),
+    Quit,
+    ForceQuit,
+    WriteQuit,
+    GoToLine(LineIdx),
+    // Switches to the named theme, or shows the current theme without a name.
+    Theme(Option<String>),
+    ShowFileSettings,
+    // Shows or hides whitespace, like vim's `list` option.
+    ShowWhitespace(bool),
+}
+
+impl TryFrom<&str> for ExCommand {
+    type Error = String;
+
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        let input = input.trim();
+        let (name, argument) = input
+            .split_once(char::is_whitespace)
+            .map_or((input, None), |(name, argument)| {
+                (name, Some(argument.trim().to_string()))
+            });
+        match (name, argument) {
+            ("w" | "write", file_name) => Ok(Self::Write(file_name)),
+            ("q" | "quit", None) => Ok(Self::Quit),
+            ("q!" | "quit!", None) => Ok(Self::ForceQuit),
+            ("wq" | "x", None) => Ok(Self::WriteQuit),
+            ("colo" | "colorscheme", name) => Ok(Self::Theme(name)),
+            ("set", None) => Ok(Self::ShowFileSettings),
+            ("set", Some(option)) if option == "list" => Ok(Self::ShowWhitespace(true)),
+            ("set", Some(option)) if option == "nolist" => Ok(Self::ShowWhitespace(false)),
+            (line, None) => line
+                .parse::<usize>()
+                .map(|line| Self::GoToLine(line.saturating_sub(1)))
+                .map_err(|_| format!("Not an editor command: {input}")),
+            _ => Err(format!("Not an editor command: {input}")),
+        }
+    }
+}
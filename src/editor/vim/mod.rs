@@ -0,0 +1,810 @@
+use std::{
+    cmp::{max, min},
+    collections::VecDeque,
+    mem::take,
+};
+
+use crossterm::event::{
+    Event,
+    KeyCode::{Char, Esc, Tab},
+    KeyEvent, KeyModifiers,
+};
+
+use super::{
+    command::{Command, Edit, Fold, Move, Select, SelectionMode, System},
+    ui_components::View,
+};
+
+mod ex_command;
+mod mode;
+mod operator;
+
+pub use ex_command::ExCommand;
+pub use mode::Mode;
+use operator::Operator;
+
+// Translates key events into commands following vim's modal editing model.
+// Motions, operators and counts are resolved here, so that the editor only
+// ever sees the resulting `Command`s.
+#[derive(Default)]
+pub struct Vim {
+    mode: Mode,
+    count: Option<usize>,
+    operator: Option<(Operator, usize)>,
+    awaiting_g: bool,
+    // Whether the previous key was `z`, which prefixes fold commands.
+    awaiting_z: bool,
+    // The keys of the command which is currently being entered.
+    keys: Vec<KeyEvent>,
+    // The keys of the last completed change, replayed by `.`
+    last_change: Vec<KeyEvent>,
+    replay: VecDeque<KeyEvent>,
+    // The key waiting for a register or mark name, such as `q` or `m`, along
+    // with its count.
+    awaiting_register: Option<(char, usize)>,
+    // Whether a macro is being recorded, in which case `q` stops recording.
+    recording: bool,
+    // The register played last, which `@@` plays again.
+    last_played: Option<char>,
+}
+
+impl Vim {
+    pub const fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    // Follows selection changes made with the mouse: selecting text enters
+    // visual mode, and clearing the selection leaves it.
+    pub fn sync_selection(&mut self, has_selection: bool) {
+        match self.mode {
+            Mode::Normal if has_selection => {
+                self.reset_pending();
+                self.mode = Mode::Visual;
+            }
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock if !has_selection => {
+                self.leave_visual_mode();
+            }
+            _ => {}
+        }
+    }
+
+    // Returns the move which puts the caret back onto the last character of
+    // the line, if the commands of the previous key left it behind, such as
+    // `j` onto a shorter line or `d$`. Outside insert and visual mode, the
+    // caret can't be placed behind the line.
+    pub fn caret_correction(&self, view: &View) -> Option<Command> {
+        let location = view.text_location();
+        let line_length = view.grapheme_count(location.line_idx);
+        (self.mode == Mode::Normal && line_length > 0 && location.grapheme_idx >= line_length)
+            .then_some(Command::Move(Move::Left))
+    }
+
+    // Returns the next key to be processed as part of a `.` repetition.
+    pub fn next_replayed_key(&mut self) -> Option<KeyEvent> {
+        self.replay.pop_front()
+    }
+
+    pub fn handle_key_event(&mut self, event: KeyEvent, view: &View) -> Vec<Command> {
+        match self.mode {
+            Mode::Insert => self.handle_insert_key(event, view),
+            Mode::Normal => self.handle_normal_key(event, view),
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => {
+                self.handle_visual_key(event, view)
+            }
+        }
+    }
+
+    // region: insert mode
+
+    fn handle_insert_key(&mut self, event: KeyEvent, view: &View) -> Vec<Command> {
+        if event.code == Esc {
+            self.keys.push(event);
+            self.mode = Mode::Normal;
+            self.last_change = take(&mut self.keys);
+            return if view.text_location().grapheme_idx > 0 {
+                vec![Command::Move(Move::Left)]
+            } else {
+                Vec::new()
+            };
+        }
+        let Ok(command) = Command::try_from(Event::Key(event)) else {
+            return Vec::new();
+        };
+        // Macros are recorded and played with `q` and `@` in normal mode only
+        if matches!(
+            command,
+            Command::System(System::RecordMacro(_) | System::StopMacro | System::PlayMacro(_))
+        ) {
+            return Vec::new();
+        }
+        if matches!(command, Command::Edit(_) | Command::Move(_)) {
+            self.keys.push(event);
+        }
+        vec![command]
+    }
+
+    // region end
+
+    // region: normal mode
+
+    fn handle_normal_key(&mut self, event: KeyEvent, view: &View) -> Vec<Command> {
+        self.keys.push(event);
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        if let (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) = (code, modifiers) {
+            return self.handle_normal_char(character, view);
+        }
+        self.reset_pending();
+        if (code, modifiers) == (Char('v'), KeyModifiers::CONTROL) {
+            self.mode = Mode::VisualBlock;
+            return vec![Command::Select(Select::Begin(
+                SelectionMode::InclusiveBlock,
+            ))];
+        }
+        // Terminals report Ctrl-I as Tab
+        match (code, modifiers) {
+            (Char('o'), KeyModifiers::CONTROL) => return vec![Command::Move(Move::JumpBack)],
+            (Tab, KeyModifiers::NONE) => return vec![Command::Move(Move::JumpForward)],
+            _ => {}
+        }
+        if let Ok(movement) = Move::try_from(event) {
+            return vec![Command::Move(movement)];
+        }
+        if let Some(edit) = line_edit(event) {
+            self.finish_command(true);
+            return vec![Command::Edit(edit)];
+        }
+        if let Ok(select @ (Select::AddCaretAbove | Select::AddCaretBelow)) =
+            Select::try_from(event)
+        {
+            return vec![Command::Select(select)];
+        }
+        if let Ok(fold) = Fold::try_from(event) {
+            return vec![Command::Fold(fold)];
+        }
+        System::try_from(event)
+            .map(Command::System)
+            .into_iter()
+            .collect()
+    }
+
+    fn handle_normal_char(&mut self, character: char, view: &View) -> Vec<Command> {
+        if let Some((key, count)) = self.awaiting_register.take() {
+            self.finish_command(false);
+            return self.register_commands(key, count, character);
+        }
+        if take(&mut self.awaiting_z) {
+            self.finish_command(false);
+            return fold_command(character)
+                .map(Command::Fold)
+                .into_iter()
+                .collect();
+        }
+        if let Some(commands) = self.handle_count_and_motion(character, view) {
+            return commands;
+        }
+        let count = self.take_count();
+        if let Some((operator, operator_count)) = self.operator.take() {
+            // Only a repeated operator, such as `dd`, remains as a valid continuation.
+            if Operator::try_from(character) == Ok(operator) {
+                let commands = self.apply_operator_to_lines(
+                    operator,
+                    operator_count.saturating_mul(count),
+                    view,
+                );
+                if self.mode != Mode::Insert {
+                    self.finish_command(operator != Operator::Yank);
+                }
+                return commands;
+            }
+            self.finish_command(false);
+            return Vec::new();
+        }
+        if let Ok(operator) = Operator::try_from(character) {
+            self.operator = Some((operator, count));
+            return Vec::new();
+        }
+        if let Some(commands) = insert_commands(character, view) {
+            self.mode = Mode::Insert;
+            return commands;
+        }
+        let location = view.text_location();
+        let line_length = view.grapheme_count(location.line_idx);
+        let mut commands = Vec::new();
+        let is_change = match character {
+            'x' | 'X' => {
+                let (movement, available) = if character == 'x' {
+                    (
+                        Move::Right,
+                        line_length.saturating_sub(location.grapheme_idx),
+                    )
+                } else {
+                    (Move::Left, location.grapheme_idx)
+                };
+                let steps = min(count, available);
+                if steps > 0 {
+                    commands.push(Command::Select(Select::Begin(SelectionMode::Character)));
+                    commands.extend(repeat(movement, steps));
+                    commands.push(Command::Edit(Edit::Cut));
+                }
+                true
+            }
+            'D' | 'C' => {
+                commands.push(Command::Select(Select::Begin(SelectionMode::Character)));
+                commands.push(Command::Move(Move::EndOfLine));
+                commands.push(Command::Edit(Edit::Cut));
+                if character == 'C' {
+                    self.mode = Mode::Insert;
+                }
+                true
+            }
+            'J' => {
+                // A count joins that many lines, with a minimum of two
+                let joins = max(count.saturating_sub(1), 1);
+                commands.extend((0..joins).map(|_| Command::Edit(Edit::JoinLines)));
+                true
+            }
+            'p' | 'P' => {
+                let paste = if character == 'p' {
+                    Edit::PasteAfter
+                } else {
+                    Edit::Paste
+                };
+                commands.extend((0..count).map(|_| Command::Edit(paste.clone())));
+                true
+            }
+            'v' | 'V' => {
+                let (mode, selection_mode) = if character == 'v' {
+                    (Mode::Visual, SelectionMode::Inclusive)
+                } else {
+                    (Mode::VisualLine, SelectionMode::Line)
+                };
+                self.mode = mode;
+                commands.push(Command::Select(Select::Begin(selection_mode)));
+                false
+            }
+            'q' if self.recording => {
+                self.recording = false;
+                commands.push(Command::System(System::StopMacro));
+                false
+            }
+            'q' | '@' | 'm' | '\'' | '`' => {
+                self.awaiting_register = Some((character, count));
+                return Vec::new();
+            }
+            'z' => {
+                self.awaiting_z = true;
+                return Vec::new();
+            }
+            '.' => {
+                for _ in 0..count {
+                    self.replay.extend(self.last_change.iter().copied());
+                }
+                false
+            }
+            ':' => {
+                commands.push(Command::System(System::CommandLine));
+                false
+            }
+            '/' => {
+                commands.push(Command::System(System::Search));
+                false
+            }
+            _ => false,
+        };
+        if self.mode != Mode::Insert {
+            self.finish_command(is_change);
+        }
+        commands
+    }
+
+    // Handles keys which extend a count, or which complete a motion, in which case
+    // any pending operator is applied to it. Returns None if the key is neither.
+    fn handle_count_and_motion(&mut self, character: char, view: &View) -> Option<Vec<Command>> {
+        if let Some(digit) = character.to_digit(10) {
+            if digit > 0 || self.count.is_some() {
+                let digit = usize::try_from(digit).unwrap_or_default();
+                self.count = Some(
+                    self.count
+                        .unwrap_or_default()
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                return Some(Vec::new());
+            }
+        }
+        let explicit_count = self.count;
+        let count = self.take_count();
+        let movement = if take(&mut self.awaiting_g) {
+            if character != 'g' {
+                self.reset_pending();
+                return Some(Vec::new());
+            }
+            Move::GoToLine(explicit_count.map_or(0, |count| count.saturating_sub(1)))
+        } else if character == 'g' {
+            self.count = explicit_count;
+            self.awaiting_g = true;
+            return Some(Vec::new());
+        } else if character == 'G' {
+            Move::GoToLine(explicit_count.map_or(usize::MAX, |count| count.saturating_sub(1)))
+        } else if let Some(movement) = char_to_move(character) {
+            movement
+        } else {
+            self.count = explicit_count;
+            return None;
+        };
+        let Some((operator, operator_count)) = self.operator.take() else {
+            self.finish_command(false);
+            let past_end = self.mode != Mode::Normal;
+            return Some(motion(movement, count, view, past_end));
+        };
+        let commands = apply_operator(
+            operator,
+            movement,
+            operator_count.saturating_mul(count),
+            view,
+        );
+        if operator == Operator::Change {
+            self.mode = Mode::Insert;
+        } else {
+            self.finish_command(operator == Operator::Delete);
+        }
+        Some(commands)
+    }
+
+    fn apply_operator_to_lines(
+        &mut self,
+        operator: Operator,
+        count: usize,
+        view: &View,
+    ) -> Vec<Command> {
+        let lines_below = count.saturating_sub(1);
+        let mut commands = Vec::new();
+        if operator == Operator::Change {
+            // Keep the indentation of the first line, as vim does with autoindent.
+            commands.push(Command::Move(Move::FirstNonBlank));
+            commands.push(Command::Select(Select::Begin(SelectionMode::Character)));
+            commands.extend(motion(Move::Down, lines_below, view, true));
+            commands.push(Command::Move(Move::EndOfLine));
+            commands.push(Command::Edit(Edit::Cut));
+            self.mode = Mode::Insert;
+        } else {
+            commands.push(Command::Select(Select::Begin(SelectionMode::Line)));
+            commands.extend(motion(Move::Down, lines_below, view, true));
+            commands.extend(operator_commands(operator, SelectionMode::Line));
+        }
+        commands
+    }
+
+    // Returns the commands for a key followed by a register or mark name: `q`
+    // records a macro and `@` plays it, `m` sets a mark, `'` jumps to its line
+    // and `` ` `` to its exact location. `@@` plays the register played last,
+    // `''` and ``` `` ``` jump back.
+    fn register_commands(&mut self, key: char, count: usize, register: char) -> Vec<Command> {
+        match (key, register) {
+            ('@', '@') => {
+                let Some(register) = self.last_played else {
+                    return Vec::new();
+                };
+                return self.register_commands(key, count, register);
+            }
+            ('\'', '\'') | ('`', '`') => return vec![Command::Move(Move::JumpBack)],
+            _ if !register.is_ascii_alphanumeric() => return Vec::new(),
+            _ => {}
+        }
+        match key {
+            'q' => {
+                self.recording = true;
+                vec![Command::System(System::RecordMacro(register))]
+            }
+            'm' => vec![Command::System(System::SetMark(register))],
+            '\'' => vec![
+                Command::Move(Move::ToMark(register)),
+                Command::Move(Move::FirstNonBlank),
+            ],
+            '`' => vec![Command::Move(Move::ToMark(register))],
+            _ => {
+                self.last_played = Some(register);
+                (0..count)
+                    .map(|_| Command::System(System::PlayMacro(register)))
+                    .collect()
+            }
+        }
+    }
+
+    // region end
+
+    // region: visual mode
+
+    fn handle_visual_key(&mut self, event: KeyEvent, view: &View) -> Vec<Command> {
+        let KeyEvent {
+            code, modifiers, ..
+        } = event;
+        let selection_mode = match self.mode {
+            Mode::VisualLine => SelectionMode::Line,
+            Mode::VisualBlock => SelectionMode::InclusiveBlock,
+            Mode::Normal | Mode::Insert | Mode::Visual => SelectionMode::Inclusive,
+        };
+        let character = match (code, modifiers) {
+            (Char(character), KeyModifiers::NONE | KeyModifiers::SHIFT) => character,
+            (Esc, _) | (Char('v'), KeyModifiers::CONTROL) => {
+                self.leave_visual_mode();
+                return vec![Command::Select(Select::Clear)];
+            }
+            _ => {
+                self.reset_pending();
+                if let Some(edit) = line_edit(event) {
+                    return vec![Command::Edit(edit)];
+                }
+                return Move::try_from(event)
+                    .map(Command::Move)
+                    .into_iter()
+                    .collect();
+            }
+        };
+        if let Some(commands) = self.handle_count_and_motion(character, view) {
+            return commands;
+        }
+        self.reset_pending();
+        match character {
+            'v' | 'V' => {
+                self.leave_visual_mode();
+                vec![Command::Select(Select::Clear)]
+            }
+            'd' | 'x' | 'y' | 'c' => {
+                let operator = match character {
+                    'y' => Operator::Yank,
+                    'c' => Operator::Change,
+                    _ => Operator::Delete,
+                };
+                self.leave_visual_mode();
+                if operator == Operator::Change {
+                    self.mode = Mode::Insert;
+                }
+                operator_commands(operator, selection_mode)
+            }
+            'p' | 'P' => {
+                self.leave_visual_mode();
+                vec![Command::Edit(Edit::Paste), Command::Select(Select::Clear)]
+            }
+            'J' => {
+                self.leave_visual_mode();
+                vec![Command::Edit(Edit::JoinLines)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn leave_visual_mode(&mut self) {
+        self.mode = Mode::Normal;
+        self.keys.clear();
+    }
+
+    // region end
+
+    // region: pending state
+
+    fn take_count(&mut self) -> usize {
+        self.count.take().unwrap_or(1)
+    }
+
+    // Completes the current command, remembering its keys for `.` if it changed
+    // the text.
+    fn finish_command(&mut self, is_change: bool) {
+        let keys = take(&mut self.keys);
+        if is_change {
+            self.last_change = keys;
+        }
+    }
+
+    fn reset_pending(&mut self) {
+        self.count = None;
+        self.operator = None;
+        self.awaiting_g = false;
+        self.awaiting_z = false;
+        self.awaiting_register = None;
+        self.keys.clear();
+    }
+
+    // region end
+}
+
+// Returns the line operation for keys which act the same in every mode, such
+// as moving lines with Alt-Up and Alt-Down.
+fn line_edit(event: KeyEvent) -> Option<Edit> {
+    match Edit::try_from(event) {
+        Ok(
+            edit @ (Edit::MoveLinesUp
+            | Edit::MoveLinesDown
+            | Edit::DuplicateLines
+            | Edit::ToggleComment),
+        ) => Some(edit),
+        _ => None,
+    }
+}
+
+// Returns the commands for keys which enter insert mode, such as `a` or `o`.
+fn insert_commands(character: char, view: &View) -> Option<Vec<Command>> {
+    let location = view.text_location();
+    let commands = match character {
+        'i' => Vec::new(),
+        'a' if location.grapheme_idx < view.grapheme_count(location.line_idx) => {
+            vec![Command::Move(Move::Right)]
+        }
+        'a' => Vec::new(),
+        'I' => vec![Command::Move(Move::FirstNonBlank)],
+        'A' => vec![Command::Move(Move::EndOfLine)],
+        'o' => vec![
+            Command::Move(Move::EndOfLine),
+            Command::Edit(Edit::InsertNewLine),
+        ],
+        'O' => open_line_above().to_vec(),
+        _ => return None,
+    };
+    Some(commands)
+}
+
+// Returns the fold command completing a `z` prefix.
+const fn fold_command(character: char) -> Option<Fold> {
+    match character {
+        'c' => Some(Fold::Close),
+        'o' => Some(Fold::Open),
+        'a' => Some(Fold::Toggle),
+        'M' => Some(Fold::CloseAll),
+        'R' => Some(Fold::OpenAll),
+        _ => None,
+    }
+}
+
+const fn char_to_move(character: char) -> Option<Move> {
+    match character {
+        'h' => Some(Move::Left),
+        'j' => Some(Move::Down),
+        'k' => Some(Move::Up),
+        'l' => Some(Move::Right),
+        'w' => Some(Move::WordForward),
+        'b' => Some(Move::WordBackward),
+        'e' => Some(Move::WordEnd),
+        '0' => Some(Move::StartOfLine),
+        '^' => Some(Move::FirstNonBlank),
+        '$' => Some(Move::EndOfLine),
+        '%' => Some(Move::MatchingBracket),
+        _ => None,
+    }
+}
+
+// Motions which operate on whole lines when combined with an operator.
+const fn is_linewise(movement: Move) -> bool {
+    matches!(
+        movement,
+        Move::Up | Move::Down | Move::PageUp | Move::PageDown | Move::GoToLine(_)
+    )
+}
+
+// Motions which include the grapheme they end on when combined with an
+// operator.
+const fn is_inclusive(movement: Move) -> bool {
+    matches!(movement, Move::WordEnd | Move::MatchingBracket)
+}
+
+fn repeat(movement: Move, count: usize) -> impl Iterator<Item = Command> {
+    (0..count).map(move |_| Command::Move(movement))
+}
+
+// Resolves a motion into the moves it consists of. Horizontal motions stop at
+// the line boundaries, as they do in vim. Unless `past_end` is set, as it is
+// for operators and visual mode, they stop on the last character of the line.
+fn motion(movement: Move, count: usize, view: &View, past_end: bool) -> Vec<Command> {
+    let location = view.text_location();
+    let line_length = view.grapheme_count(location.line_idx);
+    let line_end = if past_end {
+        line_length
+    } else {
+        line_length.saturating_sub(1)
+    };
+    let count = match movement {
+        Move::Left => min(count, location.grapheme_idx),
+        Move::Right => min(count, line_end.saturating_sub(location.grapheme_idx)),
+        Move::EndOfLine if !past_end && line_length > 0 => {
+            return vec![Command::Move(Move::EndOfLine), Command::Move(Move::Left)];
+        }
+        Move::GoToLine(_)
+        | Move::StartOfLine
+        | Move::FirstNonBlank
+        | Move::EndOfLine
+        | Move::MatchingBracket => 1,
+        _ => count,
+    };
+    repeat(movement, count).collect()
+}
+
+fn apply_operator(operator: Operator, movement: Move, count: usize, view: &View) -> Vec<Command> {
+    // `cw` behaves like `ce`, which leaves the whitespace behind the word intact.
+    let movement = if operator == Operator::Change && matches!(movement, Move::WordForward) {
+        Move::WordEnd
+    } else {
+        movement
+    };
+    let selection_mode = if is_linewise(movement) {
+        SelectionMode::Line
+    } else if is_inclusive(movement) {
+        SelectionMode::Inclusive
+    } else {
+        SelectionMode::Character
+    };
+    let mut commands = vec![Command::Select(Select::Begin(selection_mode))];
+    if matches!(movement, Move::WordForward) && is_last_word_of_line(count, view) {
+        // The last word moved over ends the operated text when it is at the end
+        // of its line, rather than the first word of the next line.
+        commands.extend(repeat(movement, count.saturating_sub(1)));
+        commands.push(Command::Move(Move::EndOfLine));
+    } else {
+        commands.extend(motion(movement, count, view, true));
+    }
+    commands.extend(operator_commands(operator, selection_mode));
+    commands
+}
+
+// Whether the last of the given number of `w` motions moves from a word to the
+// next line.
+fn is_last_word_of_line(count: usize, view: &View) -> bool {
+    let mut location = view.text_location();
+    for _ in 1..count {
+        location = view.next_word_start(location);
+    }
+    location.grapheme_idx < view.grapheme_count(location.line_idx)
+        && view.next_word_start(location).line_idx > location.line_idx
+}
+
+fn operator_commands(operator: Operator, selection_mode: SelectionMode) -> Vec<Command> {
+    match operator {
+        Operator::Yank => vec![
+            Command::Edit(Edit::Copy),
+            Command::Select(Select::CollapseToStart),
+        ],
+        // Deleting a block leaves a caret on each of its lines, so that the
+        // inserted text is typed on all of them.
+        Operator::Change if selection_mode == SelectionMode::InclusiveBlock => {
+            vec![Command::Edit(Edit::Copy), Command::Edit(Edit::Delete)]
+        }
+        Operator::Change if selection_mode == SelectionMode::Line => {
+            let mut commands = vec![Command::Edit(Edit::Cut)];
+            commands.extend(open_line_above());
+            commands
+        }
+        Operator::Delete | Operator::Change => vec![Command::Edit(Edit::Cut)],
+    }
+}
+
+const fn open_line_above() -> [Command; 3] {
+    [
+        Command::Move(Move::StartOfLine),
+        Command::Edit(Edit::InsertNewLine),
+        Command::Move(Move::Up),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::{super::ClipboardEntry, *};
+    use crate::prelude::*;
+
+    const ESC: char = '\u{1b}';
+
+    // Types the keys into vim, applying the commands to a view holding the
+    // text as the editor does. Returns the resulting text and caret location.
+    fn type_keys(text: &str, keys: &str) -> (String, Location) {
+        let mut view = View::default();
+        view.handle_edit_command(Edit::InsertText(text.to_string()));
+        view.handle_move_command(Move::StartOfDocument);
+        let mut vim = Vim::default();
+        let mut clipboard = None;
+        for key in keys.chars() {
+            let code = if key == ESC { KeyCode::Esc } else { Char(key) };
+            let mut next_key = Some(KeyEvent::new(code, KeyModifiers::NONE));
+            while let Some(event) = next_key {
+                for command in vim.handle_key_event(event, &view) {
+                    apply(command, &mut view, &mut clipboard);
+                }
+                if let Some(command) = vim.caret_correction(&view) {
+                    apply(command, &mut view, &mut clipboard);
+                }
+                next_key = vim.next_replayed_key();
+            }
+        }
+        let location = view.text_location();
+        view.handle_move_command(Move::StartOfDocument);
+        view.handle_select_command(Select::Begin(SelectionMode::Character));
+        view.handle_move_command(Move::EndOfDocument);
+        let text = view.copy_selection().map(|entry| entry.text);
+        (text.unwrap_or_default(), location)
+    }
+
+    fn apply(command: Command, view: &mut View, clipboard: &mut Option<ClipboardEntry>) {
+        match command {
+            Command::Move(movement) => view.handle_move_command(movement),
+            Command::Select(select) => view.handle_select_command(select),
+            Command::Edit(Edit::Cut | Edit::CutAppend) => *clipboard = view.cut_selection(),
+            Command::Edit(Edit::Copy) => *clipboard = view.copy_selection(),
+            Command::Edit(paste @ (Edit::Paste | Edit::PasteAfter)) => {
+                if let Some(entry) = clipboard {
+                    view.paste(entry, matches!(paste, Edit::PasteAfter));
+                }
+            }
+            Command::Edit(edit) => view.handle_edit_command(edit),
+            Command::Fold(_) | Command::Mouse(_) | Command::System(_) => {}
+        }
+    }
+
+    const fn at(line_idx: LineIdx, grapheme_idx: GraphemeIdx) -> Location {
+        Location {
+            line_idx,
+            grapheme_idx,
+        }
+    }
+
+    #[test]
+    fn caret_stops_on_the_last_character() {
+        assert_eq!(type_keys("foo bar", "$"), ("foo bar".to_string(), at(0, 6)));
+        assert_eq!(type_keys("ab", "lll"), ("ab".to_string(), at(0, 1)));
+        assert_eq!(type_keys("ab\ncd", "$j"), ("ab\ncd".to_string(), at(1, 1)));
+        assert_eq!(type_keys("abc\nd", "$j"), ("abc\nd".to_string(), at(1, 0)));
+        assert_eq!(type_keys("", "$l"), (String::new(), at(0, 0)));
+    }
+
+    #[test]
+    fn commands_act_on_the_last_character() {
+        assert_eq!(type_keys("foo bar", "$x").0, "foo ba");
+        assert_eq!(type_keys("foo bar", "$db").0, "foo r");
+        assert_eq!(type_keys("foo bar", "$dh").0, "foo br");
+        assert_eq!(type_keys("foo bar", "$i!").0, "foo ba!r");
+        assert_eq!(type_keys("ab", "lllx").0, "a");
+        assert_eq!(type_keys("ab\ncd", "$jx").0, "ab\nc");
+    }
+
+    #[test]
+    fn operators_reach_the_end_of_the_line() {
+        assert_eq!(type_keys("foo bar", "wd$"), ("foo ".to_string(), at(0, 3)));
+        assert_eq!(type_keys("foo bar", "wc$baz").0, "foo baz");
+        assert_eq!(type_keys("foo bar", "wD").0, "foo ");
+        assert_eq!(type_keys("foo bar", "$a!").0, "foo bar!");
+    }
+
+    #[test]
+    fn words_at_the_end_of_a_line_stay_on_it() {
+        assert_eq!(type_keys("foo\n  bar", "$dw").0, "fo\n  bar");
+        assert_eq!(type_keys("foo\n  bar", "dw").0, "\n  bar");
+        assert_eq!(type_keys("a b\nc", "2dw").0, "\nc");
+        assert_eq!(type_keys("foo bar", "dw").0, "bar");
+        assert_eq!(type_keys("foo bar", "cwx").0, "x bar");
+    }
+
+    #[test]
+    fn counts_and_repeats_apply_to_commands() {
+        assert_eq!(type_keys("abcdef", "2x.").0, "ef");
+        assert_eq!(type_keys("a\nb\nc\nd", "2ddp").0, "c\na\nb\nd");
+        assert_eq!(type_keys("a b c", "d2w").0, "c");
+        assert_eq!(type_keys("one\ntwo", &format!("A!{ESC}j.")).0, "one!\ntwo!");
+    }
+
+    #[test]
+    fn insert_mode_is_left_onto_the_inserted_text() {
+        assert_eq!(
+            type_keys("ab", &format!("A!{ESC}")),
+            ("ab!".to_string(), at(0, 2))
+        );
+        assert_eq!(
+            type_keys("ab", &format!("i{ESC}")),
+            ("ab".to_string(), at(0, 0))
+        );
+    }
+
+    #[test]
+    fn visual_mode_selects_to_the_end_of_the_line() {
+        assert_eq!(type_keys("foo bar", "wv$d").0, "foo ");
+        assert_eq!(type_keys("foo bar", "vey$p").0, "foo barfoo");
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt::{Display, Formatter, Result};
+
+use super::super::terminal::CaretStyle;
+
+#[derive(Default, Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    VisualBlock,
+}
+
+impl Mode {
+    pub const fn caret_style(self) -> CaretStyle {
+        match self {
+            Self::Normal | Self::Visual | Self::VisualLine | Self::VisualBlock => CaretStyle::Block,
+            Self::Insert => CaretStyle::Bar,
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Normal => write!(formatter, "NORMAL"),
+            Self::Insert => write!(formatter, "INSERT"),
+            Self::Visual => write!(formatter, "VISUAL"),
+            Self::VisualLine => write!(formatter, "VISUAL LINE"),
+            Self::VisualBlock => write!(formatter, "VISUAL BLOCK"),
+        }
+    }
+}
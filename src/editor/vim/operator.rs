@@ -0,0 +1,19 @@
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+impl TryFrom<char> for Operator {
+    type Error = String;
+
+    fn try_from(character: char) -> Result<Self, Self::Error> {
+        match character {
+            'd' => Ok(Self::Delete),
+            'c' => Ok(Self::Change),
+            'y' => Ok(Self::Yank),
+            _ => Err(format!("Unsupported operator {character:?}")),
+        }
+    }
+}
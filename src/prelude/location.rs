@@ -1,6 +1,6 @@
 use super::{GraphemeIdx, LineIdx};
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct Location {
-    pub grapheme_idx: GraphemeIdx,
     pub line_idx: LineIdx,
+    pub grapheme_idx: GraphemeIdx,
 }
@@ -14,3 +14,4 @@ pub use size::Size;
 
 pub const NAME: &str = env!("CARGO_PKG_NAME");
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const DEFAULT_TAB_WIDTH: ColIdx = 4;
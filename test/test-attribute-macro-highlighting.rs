@@ -0,0 +1,22 @@
+#![allow(dead_code)]
+
+// Should be highlighted as attributes:
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "x", doc = "contains ] bracket")]
+#[rustfmt::skip] struct Foo;
+
+// Should be highlighted as macros:
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+}
+
+fn main() {
+    println!("{}", square!(2));
+    let v = vec![1, 2, 3];
+    assert_eq!(v.len() as u32, 3);
+    // Should not be highlighted as macros:
+    let different = 1 != 2;
+    let not = !different;
+}
@@ -0,0 +1,12 @@
+fn main() {
+    // Should be highlighted as strings:
+    let bytes = b"bytes with \x7f escape";
+    let c_string = c"C string\0";
+    // Should be highlighted as characters:
+    let byte = b'a';
+    let escaped_byte = b'\n';
+    let quote = b'\'';
+    // Should not be highlighted:
+    let b = 1;
+    let c = b + 1;
+}
@@ -0,0 +1,12 @@
+//! Inner doc comment, highlighted as a doc comment.
+
+/// Outer doc comment, highlighted as a doc comment.
+// A regular comment.
+//// Four slashes make a regular comment again.
+
+/** Outer block doc comment,
+which spans lines. */
+/*! Inner block doc comment */
+/* Regular block comment /** nested doc-like opener */ still regular */
+/*** Three stars make a regular comment */
+/**/ struct EmptyCommentIsNotDoc;
@@ -0,0 +1,11 @@
+fn main() {
+    // Should be highlighted as escapes:
+    let simple = "\n \r \t \\ \0 \' \"";
+    let hex = "\x41\x7F";
+    let unicode = "\u{1F600} \u{41}";
+    let chars = ['\n', '\'', '\x41', '\u{1F600}'];
+    let continued = "first line \
+        second line";
+    // Should not be highlighted as escapes:
+    let raw = r"\n \t";
+}
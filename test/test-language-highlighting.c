@@ -0,0 +1,11 @@
+#include <stdio.h>
+
+/* A block comment
+   spanning two lines */
+int main(void) {
+    unsigned long count = 10ul;
+    double ratio = 1.5e3;
+    char quote = '\'';
+    printf("Count: %lu \"quoted\"\n", count); // trailing comment
+    return count > 0 ? 0 : 1;
+}
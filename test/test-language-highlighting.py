@@ -0,0 +1,7 @@
+# A comment
+def greet(name: str, times: int = 3) -> None:
+    """Greets someone
+    a few times."""
+    for _ in range(times):
+        print('Hello, \'%s\'' % name, 0x1F, 0o17, 0b101, 1_000.5e3, 2j)
+    return None if True else False
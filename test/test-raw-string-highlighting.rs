@@ -0,0 +1,14 @@
+fn main() {
+    // Should be highlighted as strings, without escapes:
+    let raw = r"C:\path\no\escapes";
+    let hashed = r#"contains "quotes" and \n"#;
+    let more_hashes = r##"contains "# and ends here"##;
+    let raw_bytes = br#"raw bytes"#;
+    let raw_c = cr"raw C string";
+    let ml_raw = r#"spans
+several "lines"
+until here"#;
+    // Should not be highlighted as raw strings:
+    let r#type = 1;
+    let bar = r;
+}